@@ -0,0 +1,479 @@
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read};
+use std::process::{Child, Command, Stdio};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+use crate::run_command;
+
+// ===== Audio Devices (PulseAudio / PipeWire) =====
+//
+// Talks to the sound server through `pactl --format=json`, which is served by
+// both PulseAudio and pipewire-pulse. The JSON output is parsed structurally,
+// so device names and descriptions containing separators are handled safely.
+// Query results are cached until `pactl subscribe` reports a change, so
+// sampling an idle sound server spawns no processes.
+
+// PA_VOLUME_NORM: the raw volume value that corresponds to 100%.
+const VOLUME_NORM: f64 = 65536.0;
+
+#[derive(Serialize, Clone, PartialEq)]
+pub struct AudioPort {
+    pub name: String,
+    pub description: String,
+    pub available: bool,
+}
+
+#[derive(Serialize, Clone, PartialEq)]
+pub struct AudioDevice {
+    pub index: u32,
+    pub name: String,
+    pub description: String,
+    /// "sink" (output) or "source" (input)
+    pub kind: String,
+    pub volume: u32,
+    pub is_muted: bool,
+    pub is_default: bool,
+    pub ports: Vec<AudioPort>,
+    pub active_port: Option<String>,
+}
+
+#[derive(Serialize, Clone, PartialEq)]
+pub struct AudioDevices {
+    pub sinks: Vec<AudioDevice>,
+    pub sources: Vec<AudioDevice>,
+}
+
+/// A playing application stream (PulseAudio "sink input").
+#[derive(Serialize, Clone, PartialEq)]
+pub struct AudioStream {
+    pub index: u32,
+    pub app_name: String,
+    pub media_name: Option<String>,
+    pub icon: Option<String>,
+    pub volume: u32,
+    pub is_muted: bool,
+    pub is_corked: bool,
+    pub sink_index: u32,
+    pub sink_name: Option<String>,
+}
+
+fn pactl_json(args: &[&str]) -> Result<Value, String> {
+    let mut full = vec!["--format=json"];
+    full.extend_from_slice(args);
+    let output = run_command("pactl", &full)?;
+    serde_json::from_str(&output).map_err(|e| format!("Unexpected pactl output: {e}"))
+}
+
+// ===== pactl Cache =====
+
+#[derive(Default)]
+struct PactlCache {
+    // Whether `pactl subscribe` is running; without it nothing is cached.
+    watching: bool,
+    // Bumped on every event, so a query that raced with a change is not stored.
+    generation: u64,
+    // "info", "sinks", "sources" or "sink-inputs" -> last pactl output
+    entries: HashMap<&'static str, Value>,
+}
+
+static PACTL_CACHE: OnceLock<Mutex<PactlCache>> = OnceLock::new();
+
+fn pactl_cache() -> &'static Mutex<PactlCache> {
+    PACTL_CACHE.get_or_init(|| {
+        std::thread::spawn(watch_pactl);
+        Mutex::new(PactlCache::default())
+    })
+}
+
+/// Cache entries affected by a `pactl subscribe` line such as
+/// "Event 'change' on sink #52".
+fn affected_entries(event: &str) -> &'static [&'static str] {
+    let facility = event
+        .split(" on ")
+        .nth(1)
+        .and_then(|rest| rest.split(" #").next())
+        .unwrap_or_default();
+    match facility {
+        "server" => &["info"],
+        "sink" => &["sinks", "sink-inputs"],
+        "source" => &["sources"],
+        "sink-input" => &["sink-inputs"],
+        "card" => &["sinks", "sources"],
+        _ => &[],
+    }
+}
+
+fn set_watching(watching: bool) {
+    if let Ok(mut cache) = pactl_cache().lock() {
+        cache.watching = watching;
+        cache.generation += 1;
+        cache.entries.clear();
+    }
+}
+
+fn watch_pactl() {
+    loop {
+        let child = Command::new("pactl")
+            .arg("subscribe")
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn();
+        if let Ok(mut child) = child {
+            if let Some(stdout) = child.stdout.take() {
+                set_watching(true);
+                for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                    let affected = affected_entries(&line);
+                    if affected.is_empty() {
+                        continue;
+                    }
+                    if let Ok(mut cache) = pactl_cache().lock() {
+                        cache.generation += 1;
+                        for entry in affected {
+                            cache.entries.remove(entry);
+                        }
+                    }
+                }
+                set_watching(false);
+            }
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+        std::thread::sleep(Duration::from_secs(5));
+    }
+}
+
+/// `pactl --format=json info` or `list <entry>`, from the cache when possible.
+fn cached_json(entry: &'static str) -> Result<Value, String> {
+    let generation = {
+        let cache = pactl_cache().lock().map_err(|e| e.to_string())?;
+        if let Some(value) = cache.entries.get(entry) {
+            return Ok(value.clone());
+        }
+        cache.generation
+    };
+    let value = if entry == "info" {
+        pactl_json(&["info"])?
+    } else {
+        pactl_json(&["list", entry])?
+    };
+    let mut cache = pactl_cache().lock().map_err(|e| e.to_string())?;
+    if cache.watching && cache.generation == generation {
+        cache.entries.insert(entry, value.clone());
+    }
+    Ok(value)
+}
+
+/// Runs a pactl command that changes the server state. Drops the cache, so a
+/// read right after it does not wait for the subscribe event.
+pub(crate) fn pactl_set(args: &[&str]) -> Result<(), String> {
+    run_command("pactl", args)?;
+    if let Ok(mut cache) = pactl_cache().lock() {
+        cache.generation += 1;
+        cache.entries.clear();
+    }
+    Ok(())
+}
+
+/// Maps the UI-facing kind to the pactl object name.
+fn pactl_kind(kind: &str) -> Result<&'static str, String> {
+    match kind {
+        "sink" => Ok("sink"),
+        "source" => Ok("source"),
+        _ => Err(format!("Unknown audio device kind: {kind}")),
+    }
+}
+
+/// Average of all channel volumes, as a percentage of the nominal volume.
+pub(crate) fn parse_volume(volume: &Value) -> u32 {
+    let Some(channels) = volume.as_object() else {
+        return 0;
+    };
+    let values: Vec<f64> = channels
+        .values()
+        .filter_map(|c| c.get("value").and_then(Value::as_f64))
+        .collect();
+    if values.is_empty() {
+        return 0;
+    }
+    let avg = values.iter().sum::<f64>() / values.len() as f64;
+    ((avg / VOLUME_NORM) * 100.0).round().clamp(0.0, 150.0) as u32
+}
+
+fn str_field(obj: &Value, key: &str) -> String {
+    obj.get(key)
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string()
+}
+
+fn parse_device(obj: &Value, kind: &str, default_name: Option<&str>) -> AudioDevice {
+    let name = str_field(obj, "name");
+    let ports = obj
+        .get("ports")
+        .and_then(Value::as_array)
+        .map(|ports| {
+            ports
+                .iter()
+                .map(|p| AudioPort {
+                    name: str_field(p, "name"),
+                    description: str_field(p, "description"),
+                    available: p.get("availability").and_then(Value::as_str)
+                        != Some("not available"),
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    AudioDevice {
+        index: obj.get("index").and_then(Value::as_u64).unwrap_or(0) as u32,
+        description: str_field(obj, "description"),
+        kind: kind.to_string(),
+        volume: obj.get("volume").map(parse_volume).unwrap_or(0),
+        is_muted: obj.get("mute").and_then(Value::as_bool).unwrap_or(false),
+        is_default: default_name == Some(name.as_str()),
+        ports,
+        active_port: obj
+            .get("active_port")
+            .and_then(Value::as_str)
+            .map(String::from),
+        name,
+    }
+}
+
+fn is_monitor_source(obj: &Value) -> bool {
+    obj.get("monitor_of_sink")
+        .and_then(Value::as_str)
+        .map(|s| s != "n/a")
+        .unwrap_or(false)
+        || obj
+            .pointer("/properties/device.class")
+            .and_then(Value::as_str)
+            == Some("monitor")
+}
+
+pub(crate) fn default_device_name(kind: &str) -> Option<String> {
+    let kind = pactl_kind(kind).ok()?;
+    cached_json("info")
+        .ok()?
+        .get(format!("default_{kind}_name"))
+        .and_then(Value::as_str)
+        .filter(|s| !s.is_empty())
+        .map(String::from)
+}
+
+pub(crate) fn list_devices(kind: &str) -> Result<Vec<AudioDevice>, String> {
+    let entry = match pactl_kind(kind)? {
+        "sink" => "sinks",
+        _ => "sources",
+    };
+    let list = cached_json(entry)?;
+    let default_name = default_device_name(kind);
+    Ok(list
+        .as_array()
+        .map(|items| {
+            items
+                .iter()
+                .filter(|obj| kind != "source" || !is_monitor_source(obj))
+                .map(|obj| parse_device(obj, kind, default_name.as_deref()))
+                .collect()
+        })
+        .unwrap_or_default())
+}
+
+/// The current default device of the given kind.
+pub(crate) fn default_device(kind: &str) -> Result<AudioDevice, String> {
+    let devices = list_devices(kind)?;
+    devices
+        .iter()
+        .find(|d| d.is_default)
+        .or_else(|| devices.first())
+        .cloned()
+        .ok_or_else(|| format!("No audio {kind} found"))
+}
+
+#[tauri::command]
+pub fn list_audio_devices() -> Result<AudioDevices, String> {
+    Ok(AudioDevices {
+        sinks: list_devices("sink")?,
+        sources: list_devices("source")?,
+    })
+}
+
+#[tauri::command]
+pub fn set_default_audio_device(kind: &str, name: &str) -> Result<(), String> {
+    let kind = pactl_kind(kind)?;
+    pactl_set(&[&format!("set-default-{kind}"), name])?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn set_audio_device_volume(kind: &str, name: &str, volume: u32) -> Result<(), String> {
+    let kind = pactl_kind(kind)?;
+    let v = volume.min(150);
+    pactl_set(&[&format!("set-{kind}-volume"), name, &format!("{v}%")])?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn set_audio_device_mute(kind: &str, name: &str, muted: bool) -> Result<(), String> {
+    let kind = pactl_kind(kind)?;
+    pactl_set(&[
+        &format!("set-{kind}-mute"),
+        name,
+        if muted { "1" } else { "0" },
+    ])?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn set_audio_device_port(kind: &str, name: &str, port: &str) -> Result<(), String> {
+    let kind = pactl_kind(kind)?;
+    pactl_set(&[&format!("set-{kind}-port"), name, port])?;
+    Ok(())
+}
+
+// ===== Application Streams =====
+
+fn parse_stream(obj: &Value, sinks: &[AudioDevice]) -> AudioStream {
+    let prop = |key: &str| {
+        obj.pointer(&format!("/properties/{key}"))
+            .and_then(Value::as_str)
+            .filter(|s| !s.is_empty())
+            .map(String::from)
+    };
+    let binary = prop("application.process.binary");
+    let app_name = prop("application.name")
+        .or_else(|| binary.clone())
+        .unwrap_or_else(|| "Unknown".to_string());
+    let icon = prop("application.icon_name")
+        .or(binary)
+        .and_then(|name| crate::resolve_icon_path(&name));
+    let sink_index = obj.get("sink").and_then(Value::as_u64).unwrap_or(0) as u32;
+
+    AudioStream {
+        index: obj.get("index").and_then(Value::as_u64).unwrap_or(0) as u32,
+        app_name,
+        media_name: prop("media.name"),
+        icon,
+        volume: obj.get("volume").map(parse_volume).unwrap_or(0),
+        is_muted: obj.get("mute").and_then(Value::as_bool).unwrap_or(false),
+        is_corked: obj.get("corked").and_then(Value::as_bool).unwrap_or(false),
+        sink_index,
+        sink_name: sinks
+            .iter()
+            .find(|s| s.index == sink_index)
+            .map(|s| s.name.clone()),
+    }
+}
+
+#[tauri::command]
+pub fn list_audio_streams() -> Result<Vec<AudioStream>, String> {
+    let sinks = list_devices("sink").unwrap_or_default();
+    let list = cached_json("sink-inputs")?;
+    Ok(list
+        .as_array()
+        .map(|items| items.iter().map(|obj| parse_stream(obj, &sinks)).collect())
+        .unwrap_or_default())
+}
+
+#[tauri::command]
+pub fn set_audio_stream_volume(index: u32, volume: u32) -> Result<(), String> {
+    let v = volume.min(150);
+    pactl_set(&[
+        "set-sink-input-volume",
+        &index.to_string(),
+        &format!("{v}%"),
+    ])?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn set_audio_stream_mute(index: u32, muted: bool) -> Result<(), String> {
+    pactl_set(&[
+        "set-sink-input-mute",
+        &index.to_string(),
+        if muted { "1" } else { "0" },
+    ])?;
+    Ok(())
+}
+
+/// Moves an application stream to another output device (by sink name).
+#[tauri::command]
+pub fn move_audio_stream(index: u32, sink: &str) -> Result<(), String> {
+    pactl_set(&["move-sink-input", &index.to_string(), sink])?;
+    Ok(())
+}
+
+// ===== Microphone Level Meter =====
+//
+// Records the default source through `parec` at a low sample rate and emits
+// the peak level of every ~100ms chunk as `audio://mic-level` (0.0 - 1.0).
+// The meter only runs while the UI asks for it.
+
+const METER_RATE: usize = 8000;
+const METER_CHUNK_SAMPLES: usize = METER_RATE / 10;
+
+static MIC_METER: OnceLock<Mutex<Option<Child>>> = OnceLock::new();
+
+fn mic_meter() -> &'static Mutex<Option<Child>> {
+    MIC_METER.get_or_init(|| Mutex::new(None))
+}
+
+fn peak_level(chunk: &[u8]) -> f32 {
+    let peak = chunk
+        .chunks_exact(2)
+        .map(|s| i16::from_le_bytes([s[0], s[1]]).unsigned_abs())
+        .max()
+        .unwrap_or(0);
+    peak as f32 / i16::MAX as f32
+}
+
+#[tauri::command]
+pub fn start_mic_level_meter(app: AppHandle) -> Result<(), String> {
+    let mut meter = mic_meter().lock().map_err(|e| e.to_string())?;
+    if let Some(child) = meter.as_mut() {
+        if matches!(child.try_wait(), Ok(None)) {
+            return Ok(());
+        }
+    }
+
+    let rate = format!("--rate={METER_RATE}");
+    let mut child = Command::new("parec")
+        .args([
+            "--device=@DEFAULT_SOURCE@",
+            "--format=s16le",
+            "--channels=1",
+            rate.as_str(),
+            "--latency-msec=100",
+            "--raw",
+        ])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("Failed to start parec: {e}"))?;
+    let mut stdout = child.stdout.take().ok_or("parec has no stdout")?;
+    *meter = Some(child);
+
+    std::thread::spawn(move || {
+        let mut buf = vec![0u8; METER_CHUNK_SAMPLES * 2];
+        // read_exact fails once parec exits or is killed by stop_mic_level_meter.
+        while stdout.read_exact(&mut buf).is_ok() {
+            let _ = app.emit("audio://mic-level", peak_level(&buf));
+        }
+    });
+    Ok(())
+}
+
+#[tauri::command]
+pub fn stop_mic_level_meter() -> Result<(), String> {
+    let mut meter = mic_meter().lock().map_err(|e| e.to_string())?;
+    if let Some(mut child) = meter.take() {
+        let _ = child.kill();
+        let _ = child.wait();
+    }
+    Ok(())
+}
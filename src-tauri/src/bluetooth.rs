@@ -0,0 +1,884 @@
+use serde::Serialize;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use tokio::sync::oneshot;
+use zbus::blocking::{Connection, MessageIterator};
+use zbus::zvariant::{ObjectPath, OwnedObjectPath};
+use zbus::MatchRule;
+
+use crate::bus::{self, prop, Properties};
+use crate::{run_command, upower};
+
+// ===== Bluetooth (BlueZ) =====
+//
+// Talks to bluetoothd over the system bus. A listener thread turns BlueZ's
+// ObjectManager and PropertiesChanged signals into `bluetooth://*` events, and
+// a pairing agent forwards PIN/passkey prompts to the UI as
+// `bluetooth://agent-request` events, which are answered through
+// `bluetooth_agent_reply`.
+//
+// Battery levels come from BlueZ's Battery1 interface, or from UPower for
+// peripherals whose battery the kernel reports instead (HID mice and
+// keyboards). Crossing one of LOW_BATTERY_LEVELS emits
+// `bluetooth://battery-low`.
+
+const ADAPTER_INTERFACE: &str = "org.bluez.Adapter1";
+const DEVICE_INTERFACE: &str = "org.bluez.Device1";
+const BATTERY_INTERFACE: &str = "org.bluez.Battery1";
+const AGENT_PATH: &str = "/org/sis_crystal/bluetooth_agent";
+
+/// Percentages at which a connected device's battery is reported as low,
+/// highest first.
+const LOW_BATTERY_LEVELS: [u8; 3] = [20, 10, 5];
+
+type ManagedObjects = HashMap<OwnedObjectPath, HashMap<String, Properties>>;
+
+#[zbus::proxy(
+    interface = "org.freedesktop.DBus.ObjectManager",
+    default_service = "org.bluez",
+    default_path = "/"
+)]
+trait BluezObjectManager {
+    fn get_managed_objects(&self) -> zbus::Result<ManagedObjects>;
+}
+
+#[zbus::proxy(
+    interface = "org.freedesktop.DBus.Properties",
+    default_service = "org.bluez"
+)]
+trait BluezProperties {
+    fn get_all(&self, interface_name: &str) -> zbus::Result<Properties>;
+}
+
+#[zbus::proxy(interface = "org.bluez.Adapter1", default_service = "org.bluez")]
+trait Adapter {
+    fn start_discovery(&self) -> zbus::Result<()>;
+
+    fn stop_discovery(&self) -> zbus::Result<()>;
+
+    fn remove_device(&self, device: &ObjectPath<'_>) -> zbus::Result<()>;
+
+    #[zbus(property)]
+    fn set_powered(&self, value: bool) -> zbus::Result<()>;
+}
+
+#[zbus::proxy(interface = "org.bluez.Device1", default_service = "org.bluez")]
+trait Device {
+    fn connect(&self) -> zbus::Result<()>;
+
+    fn disconnect(&self) -> zbus::Result<()>;
+
+    fn pair(&self) -> zbus::Result<()>;
+
+    #[zbus(property)]
+    fn address(&self) -> zbus::Result<String>;
+
+    #[zbus(property)]
+    fn alias(&self) -> zbus::Result<String>;
+
+    #[zbus(property)]
+    fn set_trusted(&self, value: bool) -> zbus::Result<()>;
+}
+
+#[zbus::proxy(
+    interface = "org.bluez.AgentManager1",
+    default_service = "org.bluez",
+    default_path = "/org/bluez"
+)]
+trait AgentManager {
+    fn register_agent(&self, agent: &ObjectPath<'_>, capability: &str) -> zbus::Result<()>;
+
+    fn request_default_agent(&self, agent: &ObjectPath<'_>) -> zbus::Result<()>;
+}
+
+#[derive(Serialize, Clone, PartialEq)]
+pub struct BluetoothAdapter {
+    /// e.g. "hci0"
+    pub name: String,
+    pub address: String,
+    pub alias: String,
+    pub powered: bool,
+    pub discovering: bool,
+    pub discoverable: bool,
+    pub pairable: bool,
+}
+
+#[derive(Serialize, Clone, PartialEq)]
+pub struct BluetoothDevice {
+    pub address: String,
+    pub name: String,
+    /// freedesktop icon name derived from the device class, e.g. "audio-headset"
+    pub icon: Option<String>,
+    /// Adapter the device was seen on, e.g. "hci0"
+    pub adapter: String,
+    pub paired: bool,
+    pub trusted: bool,
+    pub connected: bool,
+    pub blocked: bool,
+    /// dBm; only present while the device is being discovered
+    pub rssi: Option<i16>,
+    /// Percentage, for devices that report their battery
+    pub battery: Option<u8>,
+}
+
+/// Sent with `bluetooth://battery-low` when a device drops below one of
+/// LOW_BATTERY_LEVELS.
+#[derive(Serialize, Clone)]
+pub struct BluetoothBatteryWarning {
+    pub device: BluetoothDevice,
+    /// The level that was crossed
+    pub threshold: u8,
+}
+
+/// "/org/bluez/hci0/dev_AA_BB_..." -> "hci0"
+fn adapter_name(path: &str) -> String {
+    path.trim_start_matches("/org/bluez/")
+        .split('/')
+        .next()
+        .unwrap_or_default()
+        .to_string()
+}
+
+fn parse_adapter(path: &OwnedObjectPath, props: &Properties) -> BluetoothAdapter {
+    BluetoothAdapter {
+        name: adapter_name(path.as_str()),
+        address: prop(props, "Address").unwrap_or_default(),
+        alias: prop(props, "Alias").unwrap_or_default(),
+        powered: prop(props, "Powered").unwrap_or(false),
+        discovering: prop(props, "Discovering").unwrap_or(false),
+        discoverable: prop(props, "Discoverable").unwrap_or(false),
+        pairable: prop(props, "Pairable").unwrap_or(false),
+    }
+}
+
+fn parse_device(
+    path: &OwnedObjectPath,
+    interfaces: &HashMap<String, Properties>,
+) -> Option<BluetoothDevice> {
+    let props = interfaces.get(DEVICE_INTERFACE)?;
+    let address: String = prop(props, "Address")?;
+    Some(BluetoothDevice {
+        name: prop(props, "Alias")
+            .or_else(|| prop(props, "Name"))
+            .unwrap_or_else(|| address.clone()),
+        address,
+        icon: prop(props, "Icon"),
+        adapter: adapter_name(path.as_str()),
+        paired: prop(props, "Paired").unwrap_or(false),
+        trusted: prop(props, "Trusted").unwrap_or(false),
+        connected: prop(props, "Connected").unwrap_or(false),
+        blocked: prop(props, "Blocked").unwrap_or(false),
+        rssi: prop(props, "RSSI"),
+        battery: interfaces
+            .get(BATTERY_INTERFACE)
+            .and_then(|battery| prop(battery, "Percentage")),
+    })
+}
+
+/// The Device1 and Battery1 properties of a device object.
+fn device_interfaces(conn: &Connection, path: &OwnedObjectPath) -> HashMap<String, Properties> {
+    [DEVICE_INTERFACE, BATTERY_INTERFACE]
+        .into_iter()
+        .filter_map(|interface| {
+            Some((
+                interface.to_string(),
+                object_properties(conn, path, interface)?,
+            ))
+        })
+        .collect()
+}
+
+/// First "AA:BB:CC:DD:EE:FF" in `text`, upper-cased.
+fn find_address(text: &str) -> Option<String> {
+    text.split(|c: char| !(c.is_ascii_hexdigit() || c == ':'))
+        .find(|part| part.len() == 17 && part.matches(':').count() == 5)
+        .map(|part| part.to_ascii_uppercase())
+}
+
+/// Bluetooth address of a UPower device, if it is a Bluetooth peripheral
+/// that BlueZ does not report a battery for.
+fn upower_address(device: &upower::DeviceProxyBlocking) -> Option<String> {
+    let native_path = device.native_path().ok()?;
+    // Devices backed by BlueZ's Battery1 show up with their BlueZ path.
+    if native_path.starts_with("/org/bluez/") || !device.is_present().ok()? {
+        return None;
+    }
+    device
+        .serial()
+        .ok()
+        .and_then(|serial| find_address(&serial))
+        .or_else(|| find_address(&native_path))
+}
+
+fn battery_level(percentage: f64) -> u8 {
+    percentage.clamp(0.0, 100.0).round() as u8
+}
+
+/// Bluetooth address and battery percentage of a UPower device.
+fn upower_battery(device: &upower::DeviceProxyBlocking) -> Option<(String, u8)> {
+    let address = upower_address(device)?;
+    Some((address, battery_level(device.percentage().ok()?)))
+}
+
+/// Fills in battery levels UPower knows about for connected devices.
+fn add_upower_batteries(devices: &mut [BluetoothDevice]) {
+    if !devices.iter().any(|d| d.connected && d.battery.is_none()) {
+        return;
+    }
+    let Ok(conn) = bus::system() else {
+        return;
+    };
+    let batteries: HashMap<String, u8> = upower::devices(&conn)
+        .unwrap_or_default()
+        .iter()
+        .filter_map(upower_battery)
+        .collect();
+    for device in devices.iter_mut().filter(|d| d.battery.is_none()) {
+        device.battery = batteries.get(&device.address.to_ascii_uppercase()).copied();
+    }
+}
+
+fn managed_objects(conn: &Connection) -> Result<ManagedObjects, String> {
+    BluezObjectManagerProxyBlocking::new(conn)
+        .and_then(|m| m.get_managed_objects())
+        .map_err(|e| format!("Cannot reach BlueZ: {e}"))
+}
+
+fn object_properties(
+    conn: &Connection,
+    path: &OwnedObjectPath,
+    interface: &str,
+) -> Option<Properties> {
+    BluezPropertiesProxyBlocking::new(conn, path)
+        .ok()?
+        .get_all(interface)
+        .ok()
+}
+
+/// The adapter with the given name, or the first one.
+fn adapter_path(conn: &Connection, adapter: Option<&str>) -> Result<OwnedObjectPath, String> {
+    let mut adapters: Vec<OwnedObjectPath> = managed_objects(conn)?
+        .into_iter()
+        .filter(|(_, interfaces)| interfaces.contains_key(ADAPTER_INTERFACE))
+        .map(|(path, _)| path)
+        .collect();
+    adapters.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+    match adapter {
+        Some(name) => adapters
+            .into_iter()
+            .find(|p| adapter_name(p.as_str()) == name)
+            .ok_or_else(|| format!("Bluetooth adapter not found: {name}")),
+        None => adapters
+            .into_iter()
+            .next()
+            .ok_or_else(|| "No Bluetooth adapter found".to_string()),
+    }
+}
+
+fn device_path(conn: &Connection, address: &str) -> Result<OwnedObjectPath, String> {
+    managed_objects(conn)?
+        .into_iter()
+        .find(|(_, interfaces)| {
+            interfaces
+                .get(DEVICE_INTERFACE)
+                .and_then(|props| prop::<String>(props, "Address"))
+                .is_some_and(|a| a.eq_ignore_ascii_case(address))
+        })
+        .map(|(path, _)| path)
+        .ok_or_else(|| format!("Bluetooth device not found: {address}"))
+}
+
+fn device_proxy(address: &str) -> Result<DeviceProxyBlocking<'static>, String> {
+    let conn = bus::system()?;
+    let path = device_path(&conn, address)?;
+    DeviceProxyBlocking::new(&conn, path).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn list_bluetooth_adapters() -> Result<Vec<BluetoothAdapter>, String> {
+    let conn = bus::system()?;
+    let mut adapters: Vec<BluetoothAdapter> = managed_objects(&conn)?
+        .iter()
+        .filter_map(|(path, interfaces)| {
+            Some(parse_adapter(path, interfaces.get(ADAPTER_INTERFACE)?))
+        })
+        .collect();
+    adapters.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(adapters)
+}
+
+#[tauri::command]
+pub fn get_bluetooth_devices() -> Result<Vec<BluetoothDevice>, String> {
+    let conn = bus::system()?;
+    let mut devices: Vec<BluetoothDevice> = managed_objects(&conn)?
+        .iter()
+        .filter_map(|(path, interfaces)| parse_device(path, interfaces))
+        .collect();
+    add_upower_batteries(&mut devices);
+    // Connected first, then paired, then the strongest signal.
+    devices.sort_by(|a, b| {
+        b.connected
+            .cmp(&a.connected)
+            .then(b.paired.cmp(&a.paired))
+            .then(b.rssi.cmp(&a.rssi))
+            .then(a.name.cmp(&b.name))
+    });
+    Ok(devices)
+}
+
+#[tauri::command]
+pub fn set_bluetooth_powered(powered: bool, adapter: Option<String>) -> Result<(), String> {
+    let conn = bus::system()?;
+    let path = adapter_path(&conn, adapter.as_deref())?;
+    let proxy = AdapterProxyBlocking::new(&conn, path).map_err(|e| e.to_string())?;
+    if proxy.set_powered(powered).is_ok() {
+        return Ok(());
+    }
+    // Powering on fails while the radio is soft-blocked by rfkill.
+    if powered {
+        run_command("rfkill", &["unblock", "bluetooth"])?;
+    }
+    proxy.set_powered(powered).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn start_bluetooth_discovery(adapter: Option<String>) -> Result<(), String> {
+    let conn = bus::system()?;
+    let path = adapter_path(&conn, adapter.as_deref())?;
+    AdapterProxyBlocking::new(&conn, path)
+        .and_then(|a| a.start_discovery())
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn stop_bluetooth_discovery(adapter: Option<String>) -> Result<(), String> {
+    let conn = bus::system()?;
+    let path = adapter_path(&conn, adapter.as_deref())?;
+    AdapterProxyBlocking::new(&conn, path)
+        .and_then(|a| a.stop_discovery())
+        .map_err(|e| e.to_string())
+}
+
+// Pairing and connecting take seconds (pairing waits for the user to answer
+// the agent), so these run off the main thread.
+
+#[tauri::command]
+pub async fn pair_bluetooth_device(address: String) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        device_proxy(&address)?.pair().map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+pub async fn connect_bluetooth_device(address: String) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        device_proxy(&address)?.connect().map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+pub async fn disconnect_bluetooth_device(address: String) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        device_proxy(&address)?
+            .disconnect()
+            .map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+pub fn set_bluetooth_trusted(address: &str, trusted: bool) -> Result<(), String> {
+    device_proxy(address)?
+        .set_trusted(trusted)
+        .map_err(|e| e.to_string())
+}
+
+/// Unpairs a device and forgets it.
+#[tauri::command]
+pub fn remove_bluetooth_device(address: &str) -> Result<(), String> {
+    let conn = bus::system()?;
+    let path = device_path(&conn, address)?;
+    let adapter = adapter_name(path.as_str());
+    let adapter = adapter_path(&conn, Some(adapter.as_str()))?;
+    AdapterProxyBlocking::new(&conn, adapter)
+        .and_then(|a| a.remove_device(&path))
+        .map_err(|e| e.to_string())
+}
+
+// ===== Pairing Agent =====
+
+/// A prompt or notice from BlueZ during pairing.
+#[derive(Serialize, Clone)]
+pub struct AgentRequest {
+    /// Pass to `bluetooth_agent_reply`; 0 for notices that need no answer
+    pub id: u32,
+    /// "pin-code" / "passkey": ask the user to type a code,
+    /// "confirm-passkey" / "authorize" / "authorize-service": ask yes/no,
+    /// "display-pin-code" / "display-passkey": show `code` to type on the device
+    pub kind: String,
+    pub address: Option<String>,
+    pub name: Option<String>,
+    pub code: Option<String>,
+    /// Service UUID for "authorize-service"
+    pub service: Option<String>,
+}
+
+#[derive(Debug, zbus::DBusError)]
+#[zbus(prefix = "org.bluez.Error")]
+enum AgentError {
+    #[zbus(error)]
+    ZBus(zbus::Error),
+    Rejected(String),
+    Canceled(String),
+}
+
+// Pending prompts: request id -> channel the UI's answer is sent on. `None`
+// means the user rejected the request.
+type PendingRequests = Mutex<HashMap<u32, oneshot::Sender<Option<String>>>>;
+
+static AGENT_REQUESTS: OnceLock<PendingRequests> = OnceLock::new();
+static NEXT_REQUEST_ID: AtomicU32 = AtomicU32::new(1);
+
+fn agent_requests() -> &'static PendingRequests {
+    AGENT_REQUESTS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+struct PairingAgent {
+    app: AppHandle,
+}
+
+impl PairingAgent {
+    async fn request(
+        conn: &zbus::Connection,
+        device: &OwnedObjectPath,
+        id: u32,
+        kind: &str,
+        code: Option<String>,
+        service: Option<String>,
+    ) -> AgentRequest {
+        let proxy = DeviceProxy::new(conn, device).await.ok();
+        let (address, name) = match &proxy {
+            Some(p) => (p.address().await.ok(), p.alias().await.ok()),
+            None => (None, None),
+        };
+        AgentRequest {
+            id,
+            kind: kind.to_string(),
+            address,
+            name,
+            code,
+            service,
+        }
+    }
+
+    /// Shows a notice that needs no answer.
+    async fn show(
+        &self,
+        conn: &zbus::Connection,
+        device: &OwnedObjectPath,
+        kind: &str,
+        code: String,
+    ) {
+        let request = Self::request(conn, device, 0, kind, Some(code), None).await;
+        let _ = self.app.emit("bluetooth://agent-request", request);
+    }
+
+    /// Asks the UI and waits for `bluetooth_agent_reply`.
+    async fn ask(
+        &self,
+        conn: &zbus::Connection,
+        device: &OwnedObjectPath,
+        kind: &str,
+        code: Option<String>,
+        service: Option<String>,
+    ) -> Result<String, AgentError> {
+        let id = NEXT_REQUEST_ID.fetch_add(1, Ordering::Relaxed);
+        let (tx, rx) = oneshot::channel();
+        agent_requests()
+            .lock()
+            .map_err(|e| AgentError::Canceled(e.to_string()))?
+            .insert(id, tx);
+
+        let request = Self::request(conn, device, id, kind, code, service).await;
+        let _ = self.app.emit("bluetooth://agent-request", request);
+        match rx.await {
+            Ok(Some(value)) => Ok(value),
+            Ok(None) => Err(AgentError::Rejected("Rejected by user".to_string())),
+            Err(_) => Err(AgentError::Canceled("Request canceled".to_string())),
+        }
+    }
+}
+
+#[zbus::interface(name = "org.bluez.Agent1")]
+impl PairingAgent {
+    fn release(&self) {}
+
+    async fn request_pin_code(
+        &self,
+        #[zbus(connection)] conn: &zbus::Connection,
+        device: OwnedObjectPath,
+    ) -> Result<String, AgentError> {
+        let pin = self.ask(conn, &device, "pin-code", None, None).await?;
+        if pin.is_empty() || pin.len() > 16 {
+            return Err(AgentError::Rejected(
+                "PIN must be 1 to 16 characters".to_string(),
+            ));
+        }
+        Ok(pin)
+    }
+
+    async fn display_pin_code(
+        &self,
+        #[zbus(connection)] conn: &zbus::Connection,
+        device: OwnedObjectPath,
+        pincode: String,
+    ) {
+        self.show(conn, &device, "display-pin-code", pincode).await;
+    }
+
+    async fn request_passkey(
+        &self,
+        #[zbus(connection)] conn: &zbus::Connection,
+        device: OwnedObjectPath,
+    ) -> Result<u32, AgentError> {
+        let passkey = self.ask(conn, &device, "passkey", None, None).await?;
+        passkey
+            .trim()
+            .parse::<u32>()
+            .ok()
+            .filter(|p| *p <= 999_999)
+            .ok_or_else(|| AgentError::Rejected("Passkey must be a 6 digit number".to_string()))
+    }
+
+    async fn display_passkey(
+        &self,
+        #[zbus(connection)] conn: &zbus::Connection,
+        device: OwnedObjectPath,
+        passkey: u32,
+        _entered: u16,
+    ) {
+        self.show(conn, &device, "display-passkey", format!("{passkey:06}"))
+            .await;
+    }
+
+    async fn request_confirmation(
+        &self,
+        #[zbus(connection)] conn: &zbus::Connection,
+        device: OwnedObjectPath,
+        passkey: u32,
+    ) -> Result<(), AgentError> {
+        self.ask(
+            conn,
+            &device,
+            "confirm-passkey",
+            Some(format!("{passkey:06}")),
+            None,
+        )
+        .await
+        .map(|_| ())
+    }
+
+    async fn request_authorization(
+        &self,
+        #[zbus(connection)] conn: &zbus::Connection,
+        device: OwnedObjectPath,
+    ) -> Result<(), AgentError> {
+        self.ask(conn, &device, "authorize", None, None)
+            .await
+            .map(|_| ())
+    }
+
+    async fn authorize_service(
+        &self,
+        #[zbus(connection)] conn: &zbus::Connection,
+        device: OwnedObjectPath,
+        uuid: String,
+    ) -> Result<(), AgentError> {
+        self.ask(conn, &device, "authorize-service", None, Some(uuid))
+            .await
+            .map(|_| ())
+    }
+
+    /// BlueZ gave up waiting (timeout or the device cancelled pairing).
+    fn cancel(&self) {
+        if let Ok(mut pending) = agent_requests().lock() {
+            // Dropping the senders makes the waiting `ask` calls fail.
+            pending.clear();
+        }
+        let _ = self.app.emit("bluetooth://agent-cancel", ());
+    }
+}
+
+/// Answers an `AgentRequest`. `value` carries the PIN or passkey for
+/// "pin-code" and "passkey" requests.
+#[tauri::command]
+pub fn bluetooth_agent_reply(id: u32, accept: bool, value: Option<String>) -> Result<(), String> {
+    let sender = agent_requests()
+        .lock()
+        .map_err(|e| e.to_string())?
+        .remove(&id)
+        .ok_or("The pairing request is no longer pending")?;
+    let reply = accept.then(|| value.unwrap_or_default());
+    sender
+        .send(reply)
+        .map_err(|_| "The pairing request is no longer pending".to_string())
+}
+
+fn register_agent(conn: &Connection) -> Result<(), String> {
+    let manager = AgentManagerProxyBlocking::new(conn).map_err(|e| e.to_string())?;
+    let path = ObjectPath::from_static_str_unchecked(AGENT_PATH);
+    // Fails with AlreadyExists when re-registering on the same connection.
+    let _ = manager.register_agent(&path, "KeyboardDisplay");
+    manager
+        .request_default_agent(&path)
+        .map_err(|e| e.to_string())
+}
+
+// ===== Bluetooth Events =====
+
+// Device address -> lowest LOW_BATTERY_LEVELS entry already warned about.
+static BATTERY_WARNINGS: OnceLock<Mutex<HashMap<String, u8>>> = OnceLock::new();
+
+/// Emits `bluetooth://battery-low` once per level as a connected device's
+/// battery drains. Charging above the highest level re-arms the warnings.
+fn check_battery(app: &AppHandle, device: &BluetoothDevice) {
+    let Ok(mut warned) = BATTERY_WARNINGS
+        .get_or_init(|| Mutex::new(HashMap::new()))
+        .lock()
+    else {
+        return;
+    };
+    let threshold = device
+        .battery
+        .filter(|_| device.connected)
+        .and_then(|level| LOW_BATTERY_LEVELS.into_iter().filter(|t| level <= *t).min());
+    let Some(threshold) = threshold else {
+        warned.remove(&device.address);
+        return;
+    };
+    if warned
+        .get(&device.address)
+        .is_none_or(|last| threshold < *last)
+    {
+        warned.insert(device.address.clone(), threshold);
+        let _ = app.emit(
+            "bluetooth://battery-low",
+            BluetoothBatteryWarning {
+                device: device.clone(),
+                threshold,
+            },
+        );
+    }
+}
+
+/// Re-reads a device after one of its interfaces changed and emits it.
+fn device_changed(
+    app: &AppHandle,
+    conn: &Connection,
+    path: OwnedObjectPath,
+    known: &mut HashMap<OwnedObjectPath, String>,
+) {
+    let Some(mut device) = parse_device(&path, &device_interfaces(conn, &path)) else {
+        return;
+    };
+    add_upower_batteries(std::slice::from_mut(&mut device));
+    check_battery(app, &device);
+    known.insert(path, device.address.clone());
+    let _ = app.emit("bluetooth://device-changed", device);
+}
+
+fn handle_signal(
+    app: &AppHandle,
+    conn: &Connection,
+    msg: &zbus::Message,
+    known: &mut HashMap<OwnedObjectPath, String>,
+) {
+    let header = msg.header();
+    let (Some(member), Some(path)) = (header.member(), header.path()) else {
+        return;
+    };
+    let path = OwnedObjectPath::from(path.to_owned());
+
+    match member.as_str() {
+        "InterfacesAdded" => {
+            let Ok((path, interfaces)) = msg
+                .body()
+                .deserialize::<(OwnedObjectPath, HashMap<String, Properties>)>()
+            else {
+                return;
+            };
+            if let Some(device) = parse_device(&path, &interfaces) {
+                known.insert(path.clone(), device.address.clone());
+                let _ = app.emit("bluetooth://device-found", device);
+            } else if interfaces.contains_key(BATTERY_INTERFACE) {
+                // Battery1 appears once a device has connected its profiles.
+                device_changed(app, conn, path.clone(), known);
+            }
+            if let Some(props) = interfaces.get(ADAPTER_INTERFACE) {
+                // bluetoothd (re)started: the agent has to be registered again.
+                let _ = register_agent(conn);
+                let _ = app.emit("bluetooth://adapter-changed", parse_adapter(&path, props));
+            }
+        }
+        "InterfacesRemoved" => {
+            let Ok((path, interfaces)) = msg.body().deserialize::<(OwnedObjectPath, Vec<String>)>()
+            else {
+                return;
+            };
+            if interfaces.iter().any(|i| i == DEVICE_INTERFACE) {
+                if let Some(address) = known.remove(&path) {
+                    let _ = app.emit("bluetooth://device-removed", address);
+                }
+            } else if interfaces.iter().any(|i| i == BATTERY_INTERFACE) {
+                device_changed(app, conn, path, known);
+            }
+        }
+        "PropertiesChanged" => {
+            let Ok((interface, _, _)) = msg
+                .body()
+                .deserialize::<(String, Properties, Vec<String>)>()
+            else {
+                return;
+            };
+            if interface == DEVICE_INTERFACE || interface == BATTERY_INTERFACE {
+                device_changed(app, conn, path, known);
+            } else if interface == ADAPTER_INTERFACE {
+                if let Some(props) = object_properties(conn, &path, ADAPTER_INTERFACE) {
+                    let _ = app.emit("bluetooth://adapter-changed", parse_adapter(&path, &props));
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn listen(app: &AppHandle) -> Result<(), String> {
+    let conn = bus::system()?;
+    conn.object_server()
+        .at(AGENT_PATH, PairingAgent { app: app.clone() })
+        .map_err(|e| e.to_string())?;
+    if let Err(e) = register_agent(&conn) {
+        log::warn!("Failed to register Bluetooth agent: {e}");
+    }
+
+    let rule = MatchRule::builder()
+        .msg_type(zbus::message::Type::Signal)
+        .sender("org.bluez")
+        .map_err(|e| e.to_string())?
+        .build();
+    let signals =
+        MessageIterator::for_match_rule(rule, &conn, Some(256)).map_err(|e| e.to_string())?;
+
+    // Device path -> address, so removals can be reported by address.
+    let mut known: HashMap<OwnedObjectPath, String> = managed_objects(&conn)
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|(path, interfaces)| {
+            let device = parse_device(path, interfaces)?;
+            Some((path.clone(), device.address))
+        })
+        .collect();
+
+    for msg in signals.flatten() {
+        handle_signal(app, &conn, &msg, &mut known);
+    }
+    Err("BlueZ signal stream closed".to_string())
+}
+
+/// Follows battery changes of peripherals that only UPower reports.
+fn watch_upower_batteries(app: &AppHandle) -> Result<(), String> {
+    let conn = bus::system()?;
+    let rule = MatchRule::builder()
+        .msg_type(zbus::message::Type::Signal)
+        .sender("org.freedesktop.UPower")
+        .and_then(|b| b.interface("org.freedesktop.DBus.Properties"))
+        .and_then(|b| b.member("PropertiesChanged"))
+        .map_err(|e| e.to_string())?
+        .build();
+    let signals =
+        MessageIterator::for_match_rule(rule, &conn, Some(64)).map_err(|e| e.to_string())?;
+
+    // One proxy per device, so its static properties are only fetched once.
+    let mut devices: HashMap<OwnedObjectPath, upower::DeviceProxyBlocking<'static>> =
+        HashMap::new();
+    for msg in signals.flatten() {
+        let Ok((interface, changed, _)) = msg
+            .body()
+            .deserialize::<(String, Properties, Vec<String>)>()
+        else {
+            continue;
+        };
+        if interface != "org.freedesktop.UPower.Device" {
+            continue;
+        }
+        // UPower also reports charge rates and update times; only a new
+        // percentage matters here.
+        let Some(percentage) = prop::<f64>(&changed, "Percentage") else {
+            continue;
+        };
+        let Some(path) = msg
+            .header()
+            .path()
+            .map(|p| OwnedObjectPath::from(p.to_owned()))
+        else {
+            continue;
+        };
+        let device = match devices.entry(path) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                match upower::DeviceProxyBlocking::new(&conn, entry.key().clone()) {
+                    Ok(device) => entry.insert(device),
+                    Err(_) => continue,
+                }
+            }
+        };
+        let Some(address) = upower_address(device) else {
+            continue;
+        };
+        let battery = battery_level(percentage);
+        let Ok(bluez) = bus::system() else {
+            continue;
+        };
+        let Ok(device_path) = device_path(&bluez, &address) else {
+            continue;
+        };
+        if let Some(mut device) =
+            parse_device(&device_path, &device_interfaces(&bluez, &device_path))
+        {
+            device.battery.get_or_insert(battery);
+            check_battery(app, &device);
+            let _ = app.emit("bluetooth://device-changed", device);
+        }
+    }
+    Err("UPower signal stream closed".to_string())
+}
+
+/// Starts the BlueZ event listener and pairing agent.
+pub fn start(app: &AppHandle) {
+    let bluez_app = app.clone();
+    std::thread::spawn(move || loop {
+        if let Err(e) = listen(&bluez_app) {
+            log::warn!("Bluetooth listener stopped: {e}");
+        }
+        // Start over on a fresh connection in case the bus went away.
+        bus::reset_system();
+        std::thread::sleep(Duration::from_secs(10));
+    });
+
+    let upower_app = app.clone();
+    std::thread::spawn(move || loop {
+        if let Err(e) = watch_upower_batteries(&upower_app) {
+            log::warn!("Bluetooth battery listener stopped: {e}");
+        }
+        std::thread::sleep(Duration::from_secs(10));
+    });
+}
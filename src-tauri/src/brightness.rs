@@ -0,0 +1,517 @@
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::{logind, run_command};
+
+// ===== Brightness =====
+//
+// Every brightness control is a backend behind `BrightnessBackend`:
+// - sysfs: screen backlights (/sys/class/backlight) and keyboard backlights
+//   (/sys/class/leds/*kbd_backlight*), written through logind's
+//   Session.SetBrightness, which needs no root or udev rules.
+// - ddc: external monitors over DDC/CI through ddcutil. Setting
+//   SIS_CRYSTAL_DDCUTIL to another executable with ddcutil's command line
+//   lets a fake stand in for real monitors during testing; words after the
+//   program are passed before every command, e.g.
+//   "ddcutil --sleep-multiplier .5".
+
+const SYSFS_CLASS: &str = "/sys/class";
+
+// ddcutil detection probes every I2C bus and takes seconds.
+const DDC_DETECT_TTL: Duration = Duration::from_secs(300);
+// VCP feature code for luminance
+const VCP_BRIGHTNESS: &str = "10";
+
+#[derive(Serialize, Clone)]
+pub struct BrightnessDevice {
+    /// e.g. "backlight/intel_backlight" or "ddc/i2c-4"; pass to
+    /// `set_device_brightness`
+    pub id: String,
+    pub name: String,
+    /// "display" or "keyboard"
+    pub kind: String,
+    /// "sysfs" or "ddc"
+    pub backend: String,
+    /// Kernel backlight type: "firmware", "platform" or "raw"
+    pub backlight_type: Option<String>,
+    pub current: u32,
+    pub max: u32,
+    pub percent: u32,
+}
+
+trait BrightnessBackend {
+    fn name(&self) -> &'static str;
+
+    /// Devices of this backend, preferred one first.
+    fn devices(&self) -> Vec<BrightnessDevice>;
+
+    /// Devices with the brightness last read or set, for callers that only
+    /// need ids and maximums. Backends with slow reads override this.
+    fn known_devices(&self) -> Vec<BrightnessDevice> {
+        self.devices()
+    }
+
+    fn set_brightness(&self, device: &BrightnessDevice, percent: u32) -> Result<(), String>;
+}
+
+fn percent_of(current: u32, max: u32) -> u32 {
+    (current as f64 / max as f64 * 100.0).round() as u32
+}
+
+fn value_of(percent: u32, max: u32) -> u32 {
+    (max as f64 * f64::from(percent.min(100)) / 100.0).round() as u32
+}
+
+// ===== sysfs Backend =====
+
+struct Sysfs;
+
+fn read_u32(path: &Path) -> Option<u32> {
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+fn read_device(subsystem: &str, name: &str, kind: &str) -> Option<BrightnessDevice> {
+    let dir = Path::new(SYSFS_CLASS).join(subsystem).join(name);
+    let max = read_u32(&dir.join("max_brightness")).filter(|m| *m > 0)?;
+    let current = read_u32(&dir.join("brightness"))?.min(max);
+    Some(BrightnessDevice {
+        id: format!("{subsystem}/{name}"),
+        name: name.to_string(),
+        kind: kind.to_string(),
+        backend: "sysfs".to_string(),
+        backlight_type: fs::read_to_string(dir.join("type"))
+            .ok()
+            .map(|t| t.trim().to_string())
+            .filter(|_| subsystem == "backlight"),
+        current,
+        max,
+        percent: percent_of(current, max),
+    })
+}
+
+fn entry_names(subsystem: &str) -> Vec<String> {
+    let mut names: Vec<String> = fs::read_dir(Path::new(SYSFS_CLASS).join(subsystem))
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .collect();
+    names.sort();
+    names
+}
+
+/// Firmware interfaces are preferred over platform and raw ones, the same
+/// order the kernel documentation recommends to userspace.
+fn backlight_rank(device: &BrightnessDevice) -> u8 {
+    match device.backlight_type.as_deref() {
+        Some("firmware") => 0,
+        Some("platform") => 1,
+        _ => 2,
+    }
+}
+
+impl BrightnessBackend for Sysfs {
+    fn name(&self) -> &'static str {
+        "sysfs"
+    }
+
+    fn devices(&self) -> Vec<BrightnessDevice> {
+        let mut displays: Vec<BrightnessDevice> = entry_names("backlight")
+            .iter()
+            .filter_map(|name| read_device("backlight", name, "display"))
+            .collect();
+        displays.sort_by_key(backlight_rank);
+        let keyboards = entry_names("leds")
+            .into_iter()
+            .filter(|name| name.contains("kbd_backlight"))
+            .filter_map(|name| read_device("leds", &name, "keyboard"));
+        displays.into_iter().chain(keyboards).collect()
+    }
+
+    fn set_brightness(&self, device: &BrightnessDevice, percent: u32) -> Result<(), String> {
+        let (subsystem, name) = device
+            .id
+            .split_once('/')
+            .ok_or_else(|| format!("Invalid brightness device: {}", device.id))?;
+        let mut value = value_of(percent, device.max);
+        // Keep the screen lit; a keyboard backlight may be switched off.
+        if device.kind == "display" {
+            value = value.max(1);
+        }
+        let conn = crate::bus::system()?;
+        logind::session(&conn)?
+            .set_brightness(subsystem, name, value)
+            .map_err(|e| format!("Failed to set brightness: {e}"))
+    }
+}
+
+// ===== DDC/CI Backend =====
+
+#[derive(Clone)]
+struct DdcMonitor {
+    bus: u32,
+    name: String,
+    /// (current, max) as last read or set
+    brightness: Option<(u32, u32)>,
+}
+
+static DDC_MONITORS: Mutex<Option<(Instant, Vec<DdcMonitor>)>> = Mutex::new(None);
+
+struct Ddc {
+    program: String,
+    /// Options put before every command
+    options: Vec<String>,
+}
+
+impl Ddc {
+    fn from_env() -> Self {
+        let command = std::env::var("SIS_CRYSTAL_DDCUTIL").unwrap_or_default();
+        let mut words = command.split_whitespace().map(str::to_string);
+        Ddc {
+            program: words.next().unwrap_or_else(|| "ddcutil".to_string()),
+            options: words.collect(),
+        }
+    }
+
+    fn run(&self, args: &[&str]) -> Result<String, String> {
+        let mut all: Vec<&str> = self.options.iter().map(String::as_str).collect();
+        all.extend_from_slice(args);
+        run_command(&self.program, &all)
+    }
+
+    /// Parses `ddcutil detect --terse`:
+    ///
+    /// ```text
+    /// Display 1
+    ///    I2C bus:  /dev/i2c-4
+    ///    Monitor:  DEL:DELL U2719D:ABC123
+    /// ```
+    fn parse_detect(output: &str) -> Vec<DdcMonitor> {
+        let mut monitors = Vec::new();
+        let mut current: Option<DdcMonitor> = None;
+        for line in output.lines() {
+            let line = line.trim();
+            if line.starts_with("Display ") || line.starts_with("Invalid display") {
+                monitors.extend(current.take());
+                if line.starts_with("Display ") {
+                    current = Some(DdcMonitor {
+                        bus: u32::MAX,
+                        name: line.to_string(),
+                        brightness: None,
+                    });
+                }
+            } else if let Some(monitor) = current.as_mut() {
+                if let Some(bus) = line.strip_prefix("I2C bus:") {
+                    if let Some(bus) = bus
+                        .trim()
+                        .strip_prefix("/dev/i2c-")
+                        .and_then(|n| n.parse().ok())
+                    {
+                        monitor.bus = bus;
+                    }
+                } else if let Some(id) = line.strip_prefix("Monitor:") {
+                    // "MFG:Model:Serial"; the model is the readable part.
+                    let mut parts = id.trim().split(':');
+                    let model = parts.nth(1).map(str::trim).filter(|m| !m.is_empty());
+                    if let Some(model) = model {
+                        monitor.name = model.to_string();
+                    }
+                }
+            }
+        }
+        monitors.extend(current);
+        monitors.retain(|m| m.bus != u32::MAX);
+        monitors
+    }
+
+    fn monitors(&self) -> Vec<DdcMonitor> {
+        let Ok(mut cached) = DDC_MONITORS.lock() else {
+            return Vec::new();
+        };
+        if let Some((detected, monitors)) = cached.as_ref() {
+            if detected.elapsed() < DDC_DETECT_TTL {
+                return monitors.clone();
+            }
+        }
+        // A missing ddcutil is remembered as "no monitors" until the TTL ends.
+        let monitors = self
+            .run(&["detect", "--terse"])
+            .map(|output| Self::parse_detect(&output))
+            .unwrap_or_default();
+        *cached = Some((Instant::now(), monitors.clone()));
+        monitors
+    }
+
+    /// Parses `getvcp 10 --brief`, which prints "VCP 10 C <current> <max>".
+    fn parse_getvcp(output: &str) -> Option<(u32, u32)> {
+        let fields: Vec<&str> = output.split_whitespace().collect();
+        match fields.as_slice() {
+            ["VCP", _, "C", current, max, ..] => {
+                let max: u32 = max.parse().ok().filter(|m| *m > 0)?;
+                Some((current.parse::<u32>().ok()?.min(max), max))
+            }
+            _ => None,
+        }
+    }
+
+    /// Current and maximum brightness of the monitor on `bus`.
+    fn read(&self, bus: u32) -> Option<(u32, u32)> {
+        let output = self
+            .run(&[
+                "--bus",
+                &bus.to_string(),
+                "getvcp",
+                VCP_BRIGHTNESS,
+                "--brief",
+            ])
+            .ok()?;
+        let brightness = Self::parse_getvcp(&output)?;
+        Self::remember(bus, brightness);
+        Some(brightness)
+    }
+
+    /// Stores a monitor's brightness next to the detected monitors.
+    fn remember(bus: u32, brightness: (u32, u32)) {
+        if let Ok(mut cached) = DDC_MONITORS.lock() {
+            if let Some((_, monitors)) = cached.as_mut() {
+                if let Some(monitor) = monitors.iter_mut().find(|m| m.bus == bus) {
+                    monitor.brightness = Some(brightness);
+                }
+            }
+        }
+    }
+
+    fn device(monitor: DdcMonitor, (current, max): (u32, u32)) -> BrightnessDevice {
+        BrightnessDevice {
+            id: format!("ddc/i2c-{}", monitor.bus),
+            name: monitor.name,
+            kind: "display".to_string(),
+            backend: "ddc".to_string(),
+            backlight_type: None,
+            current,
+            max,
+            percent: percent_of(current, max),
+        }
+    }
+
+    fn forget_monitors() {
+        if let Ok(mut cached) = DDC_MONITORS.lock() {
+            *cached = None;
+        }
+    }
+}
+
+impl BrightnessBackend for Ddc {
+    fn name(&self) -> &'static str {
+        "ddc"
+    }
+
+    fn devices(&self) -> Vec<BrightnessDevice> {
+        self.monitors()
+            .into_iter()
+            .filter_map(|monitor| {
+                let brightness = self.read(monitor.bus)?;
+                Some(Self::device(monitor, brightness))
+            })
+            .collect()
+    }
+
+    // Only monitors never read before cost a getvcp round trip.
+    fn known_devices(&self) -> Vec<BrightnessDevice> {
+        self.monitors()
+            .into_iter()
+            .filter_map(|monitor| {
+                let brightness = monitor.brightness.or_else(|| self.read(monitor.bus))?;
+                Some(Self::device(monitor, brightness))
+            })
+            .collect()
+    }
+
+    fn set_brightness(&self, device: &BrightnessDevice, percent: u32) -> Result<(), String> {
+        let bus: u32 = device
+            .id
+            .strip_prefix("ddc/i2c-")
+            .and_then(|bus| bus.parse().ok())
+            .ok_or_else(|| format!("Invalid brightness device: {}", device.id))?;
+        let value = value_of(percent, device.max);
+        self.run(&[
+            "--bus",
+            &bus.to_string(),
+            "setvcp",
+            VCP_BRIGHTNESS,
+            &value.to_string(),
+        ])
+        .map(|_| Self::remember(bus, (value, device.max)))
+        .map_err(|e| {
+            // The monitor may have been unplugged; detect again next time.
+            Self::forget_monitors();
+            format!("Failed to set monitor brightness: {}", e.trim())
+        })
+    }
+}
+
+// ===== Brightness Commands =====
+
+/// Built-in panels come before external monitors.
+fn backends() -> Vec<Box<dyn BrightnessBackend>> {
+    vec![Box::new(Sysfs), Box::new(Ddc::from_env())]
+}
+
+/// Every device, read again when `fresh`, otherwise as last known.
+fn list_devices(fresh: bool) -> Vec<BrightnessDevice> {
+    let mut devices: Vec<BrightnessDevice> = backends()
+        .iter()
+        .flat_map(|b| {
+            if fresh {
+                b.devices()
+            } else {
+                b.known_devices()
+            }
+        })
+        .collect();
+    // Displays first, keeping each backend's order.
+    devices.sort_by_key(|d| d.kind != "display");
+    devices
+}
+
+fn set_device(device: &BrightnessDevice, percent: u32) -> Result<(), String> {
+    backends()
+        .iter()
+        .find(|b| b.name() == device.backend)
+        .ok_or_else(|| format!("Unknown brightness backend: {}", device.backend))?
+        .set_brightness(device, percent)
+}
+
+/// The built-in panel, or every external monitor when there is none.
+fn main_displays(fresh: bool) -> Result<Vec<BrightnessDevice>, String> {
+    let displays: Vec<BrightnessDevice> = list_devices(fresh)
+        .into_iter()
+        .filter(|d| d.kind == "display")
+        .collect();
+    let panel = displays.iter().find(|d| d.backend == "sysfs").cloned();
+    match panel {
+        Some(panel) => Ok(vec![panel]),
+        None if !displays.is_empty() => Ok(displays),
+        None => Err("No display with adjustable brightness found".to_string()),
+    }
+}
+
+// DDC/CI round trips take up to a second per monitor, so these commands run
+// off the main thread.
+
+/// Every screen and keyboard backlight and every DDC/CI monitor, screens
+/// first.
+#[tauri::command]
+pub async fn list_brightness_devices() -> Result<Vec<BrightnessDevice>, String> {
+    tauri::async_runtime::spawn_blocking(|| list_devices(true))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn set_device_brightness(device: String, percent: u32) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let target = list_devices(false)
+            .into_iter()
+            .find(|d| d.id == device)
+            .ok_or_else(|| format!("Brightness device not found: {device}"))?;
+        set_device(&target, percent)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Brightness of the main screen in percent.
+#[tauri::command]
+pub async fn get_brightness() -> Result<u32, String> {
+    tauri::async_runtime::spawn_blocking(|| {
+        main_displays(true)?
+            .first()
+            .map(|d| d.percent)
+            .ok_or_else(|| "No display with adjustable brightness found".to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Sets the built-in panel, or all external monitors on a desktop.
+#[tauri::command]
+pub async fn set_brightness(brightness: u32) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        main_displays(false)?
+            .iter()
+            .try_for_each(|d| set_device(d, brightness))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FAKE_DDCUTIL: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fake-ddcutil");
+
+    #[test]
+    fn detect_skips_invalid_displays() {
+        let monitors = Ddc::parse_detect(
+            "Display 1\n   I2C bus:  /dev/i2c-4\n   Monitor:  DEL:DELL U2719D:ABC123\n\n\
+             Invalid display\n   I2C bus:  /dev/i2c-7\n   Monitor:  ACI:ASUS VS248:\n\n\
+             Display 2\n   I2C bus:  /dev/i2c-6\n   Monitor:  GSM::\n",
+        );
+        let found: Vec<(u32, &str)> = monitors.iter().map(|m| (m.bus, m.name.as_str())).collect();
+        assert_eq!(found, vec![(4, "DELL U2719D"), (6, "Display 2")]);
+    }
+
+    #[test]
+    fn getvcp_reports_current_and_max() {
+        assert_eq!(Ddc::parse_getvcp("VCP 10 C 30 100\n"), Some((30, 100)));
+        // Out of range values are clamped; a zero maximum is unusable.
+        assert_eq!(Ddc::parse_getvcp("VCP 10 C 120 100"), Some((100, 100)));
+        assert_eq!(Ddc::parse_getvcp("VCP 10 C 0 0"), None);
+        assert_eq!(Ddc::parse_getvcp("VCP 10 ERR"), None);
+    }
+
+    #[test]
+    fn setting_brightness_reuses_the_known_maximum() {
+        let log = std::env::temp_dir().join(format!("fake-ddcutil-{}.log", std::process::id()));
+        let _ = fs::remove_file(&log);
+        let ddc = Ddc {
+            program: FAKE_DDCUTIL.to_string(),
+            options: vec!["--log".to_string(), log.to_string_lossy().to_string()],
+        };
+        // The only test that detects monitors; start from an empty cache.
+        Ddc::forget_monitors();
+
+        let devices = ddc.devices();
+        let found: Vec<(&str, u32, u32)> = devices
+            .iter()
+            .map(|d| (d.id.as_str(), d.percent, d.max))
+            .collect();
+        assert_eq!(found, vec![("ddc/i2c-4", 30, 100), ("ddc/i2c-6", 63, 80)]);
+
+        let monitor = ddc
+            .known_devices()
+            .into_iter()
+            .find(|d| d.id == "ddc/i2c-6")
+            .unwrap();
+        ddc.set_brightness(&monitor, 50).unwrap();
+        assert_eq!(ddc.known_devices()[1].current, 40);
+        Ddc::forget_monitors();
+
+        let calls = fs::read_to_string(&log).unwrap();
+        let _ = fs::remove_file(&log);
+        assert_eq!(
+            calls.lines().collect::<Vec<_>>(),
+            vec![
+                "detect --terse",
+                "--bus 4 getvcp 10 --brief",
+                "--bus 6 getvcp 10 --brief",
+                "--bus 6 setvcp 10 40",
+            ]
+        );
+    }
+}
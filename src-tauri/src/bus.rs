@@ -0,0 +1,40 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use zbus::blocking::Connection;
+use zbus::zvariant::OwnedValue;
+
+// ===== System Bus =====
+//
+// The system bus connection shared by the BlueZ, UPower, logind,
+// power-profiles-daemon and NetworkManager clients, and the lookup they use
+// on a{sv} property maps.
+
+/// Properties as returned by GetAll or in InterfacesAdded (a{sv}).
+pub type Properties = HashMap<String, OwnedValue>;
+
+static SYSTEM_BUS: Mutex<Option<Connection>> = Mutex::new(None);
+
+/// Shared connection to the system bus, opened on first use.
+pub fn system() -> Result<Connection, String> {
+    let mut cached = SYSTEM_BUS.lock().map_err(|e| e.to_string())?;
+    if let Some(conn) = cached.as_ref() {
+        return Ok(conn.clone());
+    }
+    let conn = Connection::system().map_err(|e| format!("Cannot reach the system bus: {e}"))?;
+    *cached = Some(conn.clone());
+    Ok(conn)
+}
+
+/// Drops the shared connection so the next caller opens a fresh one, e.g.
+/// after a listener noticed the bus went away.
+pub fn reset_system() {
+    if let Ok(mut cached) = SYSTEM_BUS.lock() {
+        *cached = None;
+    }
+}
+
+/// Reads and converts one property; `None` when it is missing or has
+/// another type.
+pub fn prop<T: TryFrom<OwnedValue>>(props: &Properties, key: &str) -> Option<T> {
+    T::try_from(props.get(key)?.try_clone().ok()?).ok()
+}
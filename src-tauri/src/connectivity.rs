@@ -0,0 +1,112 @@
+use serde::Serialize;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::nm::{self, NetworkManagerProxyBlocking};
+use crate::run_command;
+
+// ===== Connectivity & Captive Portals =====
+//
+// NetworkManager periodically fetches its check URI to tell "associated"
+// apart from "on the internet". When it reports a portal, the check URI is
+// fetched once more without following redirects; the redirect target is the
+// portal's login page.
+
+// Used when NetworkManager has no check URI configured.
+const FALLBACK_CHECK_URI: &str = "http://nmcheck.gnome.org/check_network_status.txt";
+const PORTAL_CACHE_DURATION: Duration = Duration::from_secs(60);
+
+#[derive(Serialize, Clone, PartialEq)]
+pub struct ConnectivityInfo {
+    /// "unknown", "none", "portal", "limited" or "full"
+    pub state: String,
+    /// False when connectivity checking is disabled in NetworkManager, in
+    /// which case "full" only means a default route exists
+    pub check_enabled: bool,
+    /// Page to open to sign in to a captive portal
+    pub portal_url: Option<String>,
+}
+
+static PORTAL_URL: Mutex<Option<(Instant, Option<String>)>> = Mutex::new(None);
+
+fn check_uri(manager: &NetworkManagerProxyBlocking) -> String {
+    manager
+        .connectivity_check_uri()
+        .ok()
+        .filter(|u| !u.is_empty())
+        .unwrap_or_else(|| FALLBACK_CHECK_URI.to_string())
+}
+
+/// The portal's login page, or `None` when the check URI could not be
+/// fetched at all.
+fn portal_url(check_uri: &str) -> Option<String> {
+    if let Some((at, url)) = PORTAL_URL.lock().ok().and_then(|p| p.clone()) {
+        if at.elapsed() < PORTAL_CACHE_DURATION {
+            return url;
+        }
+    }
+    let url = run_command(
+        "curl",
+        &[
+            "-sS",
+            "--max-time",
+            "5",
+            "-o",
+            "/dev/null",
+            "-w",
+            "%{redirect_url}",
+            check_uri,
+        ],
+    )
+    .ok()
+    .map(|s| {
+        let redirect = s.trim();
+        if redirect.starts_with("http://") || redirect.starts_with("https://") {
+            redirect.to_string()
+        } else {
+            // Portals that intercept without redirecting serve their login
+            // page on the check URI itself.
+            check_uri.to_string()
+        }
+    });
+    if let Ok(mut cached) = PORTAL_URL.lock() {
+        *cached = Some((Instant::now(), url.clone()));
+    }
+    url
+}
+
+fn connectivity_info(manager: &NetworkManagerProxyBlocking, state: u32) -> ConnectivityInfo {
+    let state = nm::connectivity_name(state);
+    let portal_url = if state == "portal" {
+        portal_url(&check_uri(manager))
+    } else {
+        if let Ok(mut cached) = PORTAL_URL.lock() {
+            *cached = None;
+        }
+        None
+    };
+    ConnectivityInfo {
+        state: state.to_string(),
+        check_enabled: manager.connectivity_check_enabled().unwrap_or(false),
+        portal_url,
+    }
+}
+
+/// Connectivity as last determined by NetworkManager.
+#[tauri::command]
+pub fn get_connectivity() -> Result<ConnectivityInfo, String> {
+    let conn = nm::connection()?;
+    let manager = nm::manager(&conn)?;
+    let state = manager.connectivity().map_err(|e| e.to_string())?;
+    Ok(connectivity_info(&manager, state))
+}
+
+/// Makes NetworkManager re-check connectivity now (e.g. after signing in to
+/// a portal) and returns the result.
+#[tauri::command]
+pub fn check_connectivity() -> Result<ConnectivityInfo, String> {
+    let conn = nm::connection()?;
+    let manager = nm::manager(&conn)?;
+    let state = manager.check_connectivity().map_err(|e| e.to_string())?;
+    Ok(connectivity_info(&manager, state))
+}
@@ -0,0 +1,450 @@
+use qrcode::render::svg;
+use qrcode::QrCode;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io::Read;
+use zbus::blocking::Connection;
+use zbus::zvariant::{ObjectPath, OwnedObjectPath, Value};
+
+use crate::network::{
+    profile_path, resolve_wifi_device, saved_profiles, setting, update_profile, wifi_device,
+};
+use crate::nm::{
+    self, ActiveConnectionProxyBlocking, ConnectionSettings, DeviceProxyBlocking,
+    SettingsConnectionProxyBlocking,
+};
+
+// ===== Wi-Fi Hotspot & Sharing =====
+//
+// The hotspot uses an access point ("ap" mode) profile named "Hotspot", the
+// same name nmcli and GNOME Settings use, so a hotspot configured in either
+// place is picked up here.
+
+const HOTSPOT_ID: &str = "Hotspot";
+const WIFI_SECURITY: &str = "802-11-wireless-security";
+
+#[derive(Serialize, Clone)]
+pub struct HotspotStatus {
+    pub active: bool,
+    pub ssid: Option<String>,
+    pub interface: Option<String>,
+}
+
+/// Credentials of a Wi-Fi network, ready to show to a guest.
+#[derive(Serialize, Clone)]
+pub struct WifiShareCode {
+    pub ssid: String,
+    /// "WPA", "SAE", "WEP" or "nopass"
+    pub security: String,
+    pub password: Option<String>,
+    /// The `WIFI:T:...;S:...;P:...;;` string encoded in the QR code
+    pub payload: String,
+    /// QR code as an SVG document
+    pub svg: String,
+}
+
+/// The access point profile to use, preferring one named "Hotspot". Client
+/// profiles are never picked, whatever their name.
+fn hotspot_profile(conn: &Connection) -> Result<Option<OwnedObjectPath>, String> {
+    Ok(saved_profiles(conn)?
+        .into_iter()
+        .filter(|(_, settings)| {
+            setting::<String>(settings, "802-11-wireless", "mode").as_deref() == Some("ap")
+        })
+        .max_by_key(|(_, settings)| {
+            setting::<String>(settings, "connection", "id").as_deref() == Some(HOTSPOT_ID)
+        })
+        .map(|(path, _)| path))
+}
+
+/// The active connection using the hotspot profile, with its device.
+fn active_hotspot(conn: &Connection) -> Option<(OwnedObjectPath, Option<OwnedObjectPath>)> {
+    let profile = hotspot_profile(conn).ok()??;
+    nm::manager(conn)
+        .ok()?
+        .active_connections()
+        .ok()?
+        .into_iter()
+        .find_map(|path| {
+            let active = ActiveConnectionProxyBlocking::new(conn, &path).ok()?;
+            if active.connection().ok()? != profile {
+                return None;
+            }
+            let device = active.devices().ok().and_then(|d| d.into_iter().next());
+            Some((path.clone(), device))
+        })
+}
+
+/// Random WPA passphrase without look-alike characters.
+fn generate_password() -> Result<String, String> {
+    const CHARSET: &[u8] = b"abcdefghjkmnpqrstuvwxyzABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+    let mut bytes = [0u8; 12];
+    std::fs::File::open("/dev/urandom")
+        .and_then(|mut f| f.read_exact(&mut bytes))
+        .map_err(|e| format!("Failed to generate a password: {e}"))?;
+    Ok(bytes
+        .iter()
+        .map(|b| CHARSET[*b as usize % CHARSET.len()] as char)
+        .collect())
+}
+
+fn hotspot_settings<'a>(
+    ssid: &str,
+    password: &'a str,
+    band: Option<&'a str>,
+) -> HashMap<&'static str, HashMap<&'static str, Value<'a>>> {
+    let mut connection: HashMap<&str, Value> = HashMap::new();
+    connection.insert("id", Value::from(HOTSPOT_ID));
+    connection.insert("type", Value::from("802-11-wireless"));
+    connection.insert("autoconnect", Value::from(false));
+
+    let mut wireless: HashMap<&str, Value> = HashMap::new();
+    wireless.insert("ssid", Value::from(ssid.as_bytes().to_vec()));
+    wireless.insert("mode", Value::from("ap"));
+    if let Some(band) = band {
+        wireless.insert("band", Value::from(band));
+    }
+
+    let mut security: HashMap<&str, Value> = HashMap::new();
+    security.insert("key-mgmt", Value::from("wpa-psk"));
+    security.insert("psk", Value::from(password));
+    security.insert("proto", Value::from(vec!["rsn"]));
+    security.insert("pairwise", Value::from(vec!["ccmp"]));
+    security.insert("group", Value::from(vec!["ccmp"]));
+
+    // "shared" makes NetworkManager run DHCP and NAT for the clients.
+    let mut ipv4: HashMap<&str, Value> = HashMap::new();
+    ipv4.insert("method", Value::from("shared"));
+    let mut ipv6: HashMap<&str, Value> = HashMap::new();
+    ipv6.insert("method", Value::from("ignore"));
+
+    let mut settings = HashMap::new();
+    settings.insert("connection", connection);
+    settings.insert("802-11-wireless", wireless);
+    settings.insert(WIFI_SECURITY, security);
+    settings.insert("ipv4", ipv4);
+    settings.insert("ipv6", ipv6);
+    settings
+}
+
+fn profile_ssid(conn: &Connection, path: &OwnedObjectPath) -> Option<String> {
+    let settings = SettingsConnectionProxyBlocking::new(conn, path)
+        .ok()?
+        .get_settings()
+        .ok()?;
+    setting::<Vec<u8>>(&settings, "802-11-wireless", "ssid")
+        .map(|ssid| String::from_utf8_lossy(&ssid).to_string())
+}
+
+fn profile_secret(conn: &Connection, path: &OwnedObjectPath) -> Option<String> {
+    let secrets = SettingsConnectionProxyBlocking::new(conn, path)
+        .ok()?
+        .get_secrets(WIFI_SECURITY)
+        .ok()?;
+    setting::<String>(&secrets, WIFI_SECURITY, "psk")
+        .or_else(|| setting(&secrets, WIFI_SECURITY, "wep-key0"))
+        .filter(|s| !s.is_empty())
+}
+
+/// Writes the SSID, password and band asked for into a saved access point
+/// profile. Its name, security scheme and IP settings stay as the user set
+/// them up.
+fn update_hotspot(
+    saved: &mut ConnectionSettings,
+    ssid: Option<&str>,
+    password: Option<&str>,
+    band: Option<&str>,
+) -> Result<(), String> {
+    let wireless = saved.entry("802-11-wireless".to_string()).or_default();
+    if let Some(ssid) = ssid {
+        wireless.insert("ssid".to_string(), nm::owned(ssid.as_bytes().to_vec())?);
+    }
+    if let Some(band) = band {
+        wireless.insert("band".to_string(), nm::owned(band)?);
+    }
+    if let Some(password) = password {
+        let security = saved.entry(WIFI_SECURITY.to_string()).or_default();
+        // An open access point gets the scheme a new hotspot would have.
+        if !security.contains_key("key-mgmt") {
+            security.insert("key-mgmt".to_string(), nm::owned("wpa-psk")?);
+        }
+        security.insert("psk".to_string(), nm::owned(password)?);
+    }
+    Ok(())
+}
+
+/// Activates the hotspot on `device` and returns its SSID.
+fn start_hotspot_on(
+    conn: &Connection,
+    device: &OwnedObjectPath,
+    ssid: Option<String>,
+    password: Option<String>,
+    band: Option<&str>,
+) -> Result<Option<String>, String> {
+    let ssid = ssid.filter(|s| !s.is_empty());
+    if ssid.as_ref().is_some_and(|s| s.len() > 32) {
+        return Err("SSID must be at most 32 bytes".to_string());
+    }
+    let password = password.filter(|p| !p.is_empty());
+    if password
+        .as_ref()
+        .is_some_and(|p| !(8..=63).contains(&p.len()))
+    {
+        return Err("Hotspot password must be 8 to 63 characters".to_string());
+    }
+
+    let manager = nm::manager(conn)?;
+    let none = ObjectPath::from_static_str_unchecked("/");
+    match hotspot_profile(conn)? {
+        Some(path) => {
+            if ssid.is_some() || password.is_some() || band.is_some() {
+                update_profile(conn, &path, |saved| {
+                    update_hotspot(saved, ssid.as_deref(), password.as_deref(), band)
+                })?;
+            }
+            manager
+                .activate_connection(&path, device, &none)
+                .map_err(|e| e.to_string())?;
+            Ok(ssid.or_else(|| profile_ssid(conn, &path)))
+        }
+        None => {
+            let ssid = ssid
+                .or_else(sysinfo::System::host_name)
+                .filter(|s| s.len() <= 32)
+                .unwrap_or_else(|| "SisCrystal".to_string());
+            let password = match password {
+                Some(password) => password,
+                None => generate_password()?,
+            };
+            manager
+                .add_and_activate_connection(
+                    hotspot_settings(&ssid, &password, band),
+                    device,
+                    &none,
+                )
+                .map_err(|e| e.to_string())?;
+            Ok(Some(ssid))
+        }
+    }
+}
+
+/// Starts (or reconfigures and restarts) the hotspot. Without a password the
+/// saved one is kept, or a new one is generated.
+#[tauri::command]
+pub fn start_hotspot(
+    ssid: Option<String>,
+    password: Option<String>,
+    band: Option<String>,
+    interface: Option<String>,
+) -> Result<HotspotStatus, String> {
+    let band = match band.as_deref() {
+        None | Some("") => None,
+        Some("bg") => Some("bg"),
+        Some("a") => Some("a"),
+        Some(other) => return Err(format!("Unknown Wi-Fi band: {other}")),
+    };
+    let conn = nm::connection()?;
+    let device = resolve_wifi_device(&conn, interface.as_deref())?;
+    let ssid = start_hotspot_on(&conn, &device, ssid, password, band)?;
+
+    Ok(HotspotStatus {
+        active: true,
+        ssid,
+        interface: DeviceProxyBlocking::new(&conn, &device)
+            .and_then(|d| d.interface())
+            .ok(),
+    })
+}
+
+#[tauri::command]
+pub fn stop_hotspot() -> Result<(), String> {
+    let conn = nm::connection()?;
+    let Some((active, _)) = active_hotspot(&conn) else {
+        return Ok(());
+    };
+    nm::manager(&conn)?
+        .deactivate_connection(&active)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_hotspot_status() -> Result<HotspotStatus, String> {
+    let conn = nm::connection()?;
+    let Some((active, device)) = active_hotspot(&conn) else {
+        return Ok(HotspotStatus {
+            active: false,
+            ssid: None,
+            interface: None,
+        });
+    };
+    let profile = ActiveConnectionProxyBlocking::new(&conn, &active)
+        .and_then(|a| a.connection())
+        .ok();
+    Ok(HotspotStatus {
+        active: true,
+        ssid: profile.and_then(|p| profile_ssid(&conn, &p)),
+        interface: device.and_then(|d| crate::network::device_interface(&conn, &d)),
+    })
+}
+
+// ===== Wi-Fi QR Codes =====
+
+/// Escapes the characters that are special in the `WIFI:` URI format.
+fn escape_wifi_field(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '\\' | ';' | ',' | ':' | '"') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+fn wifi_share_code(
+    settings: &ConnectionSettings,
+    password: Option<String>,
+) -> Result<WifiShareCode, String> {
+    let ssid = setting::<Vec<u8>>(settings, "802-11-wireless", "ssid")
+        .map(|ssid| String::from_utf8_lossy(&ssid).to_string())
+        .ok_or("Not a Wi-Fi connection")?;
+    let key_mgmt = setting::<String>(settings, WIFI_SECURITY, "key-mgmt");
+    let security = match key_mgmt.as_deref() {
+        None | Some("owe") => "nopass",
+        Some("sae") => "SAE",
+        Some("none") => "WEP",
+        Some("wpa-eap") | Some("wpa-eap-suite-b-192") | Some("ieee8021x") => {
+            return Err("Enterprise networks cannot be shared with a QR code".to_string())
+        }
+        Some(_) => "WPA",
+    };
+    if security != "nopass" && password.is_none() {
+        return Err("The password of this network is not available".to_string());
+    }
+    let hidden = setting::<bool>(settings, "802-11-wireless", "hidden").unwrap_or(false);
+
+    let mut payload = format!("WIFI:T:{security};S:{};", escape_wifi_field(&ssid));
+    if let Some(password) = password.as_deref().filter(|_| security != "nopass") {
+        payload.push_str(&format!("P:{};", escape_wifi_field(password)));
+    }
+    if hidden {
+        payload.push_str("H:true;");
+    }
+    payload.push(';');
+
+    let svg = QrCode::new(payload.as_bytes())
+        .map_err(|e| format!("Failed to encode QR code: {e}"))?
+        .render::<svg::Color>()
+        .min_dimensions(256, 256)
+        .build();
+    Ok(WifiShareCode {
+        ssid,
+        security: security.to_string(),
+        password,
+        payload,
+        svg,
+    })
+}
+
+/// QR code with the credentials of a saved profile, or of the network the
+/// Wi-Fi device is currently connected to when no UUID is given.
+#[tauri::command]
+pub fn get_wifi_share_code(uuid: Option<String>) -> Result<WifiShareCode, String> {
+    let conn = nm::connection()?;
+    let path = match uuid.as_deref() {
+        Some(uuid) => profile_path(&conn, uuid)?,
+        None => {
+            let device = wifi_device(&conn)?;
+            let active = DeviceProxyBlocking::new(&conn, &device)
+                .and_then(|d| d.active_connection())
+                .ok()
+                .filter(|p| !nm::is_null_path(p))
+                .ok_or("Wi-Fi is not connected")?;
+            ActiveConnectionProxyBlocking::new(&conn, &active)
+                .and_then(|a| a.connection())
+                .map_err(|e| e.to_string())?
+        }
+    };
+    let settings = SettingsConnectionProxyBlocking::new(&conn, &path)
+        .and_then(|p| p.get_settings())
+        .map_err(|e| e.to_string())?;
+    wifi_share_code(&settings, profile_secret(&conn, &path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nm_mock::{settings, MockDevice, MockNm, MockProfile};
+
+    fn profile(id: &str, uuid: &str, mode: &str) -> MockProfile {
+        MockProfile {
+            settings: settings(vec![
+                ("connection", "id", nm::owned(id).unwrap()),
+                ("connection", "uuid", nm::owned(uuid).unwrap()),
+                ("802-11-wireless", "mode", nm::owned(mode).unwrap()),
+            ]),
+            secrets: None,
+        }
+    }
+
+    #[test]
+    fn client_profiles_named_hotspot_are_ignored() {
+        let mock = MockNm {
+            profiles: vec![
+                profile(HOTSPOT_ID, "cafe-uuid", "infrastructure"),
+                profile("Guests", "guests-uuid", "ap"),
+            ],
+            ..Default::default()
+        }
+        .serve();
+
+        let found = hotspot_profile(&mock.conn).unwrap();
+        assert_eq!(found, profile_path(&mock.conn, "guests-uuid").ok());
+    }
+
+    #[test]
+    fn reused_profiles_keep_their_name_and_security() {
+        let mut guests = profile("Guests", "guests-uuid", "ap");
+        let security = guests
+            .settings
+            .entry(WIFI_SECURITY.to_string())
+            .or_default();
+        security.insert("key-mgmt".to_string(), nm::owned("sae").unwrap());
+        guests.secrets = Some(settings(vec![(
+            WIFI_SECURITY,
+            "psk",
+            nm::owned("old-password").unwrap(),
+        )]));
+        let mock = MockNm {
+            devices: vec![MockDevice {
+                interface: "wlan0",
+                device_type: nm::NM_DEVICE_TYPE_WIFI,
+                state: 30,
+                ..Default::default()
+            }],
+            profiles: vec![guests],
+            ..Default::default()
+        }
+        .serve();
+        let device = wifi_device(&mock.conn).unwrap();
+
+        let ssid = start_hotspot_on(
+            &mock.conn,
+            &device,
+            None,
+            Some("new-password".to_string()),
+            None,
+        )
+        .unwrap();
+        assert_eq!(ssid, None);
+        let calls = mock.calls.lock().unwrap();
+        let (_, updated) = &calls.updated[0];
+        let text =
+            |group: &str, key: &str| <&str>::try_from(&updated[group][key]).unwrap().to_string();
+        assert_eq!(text("connection", "id"), "Guests");
+        assert_eq!(text(WIFI_SECURITY, "key-mgmt"), "sae");
+        assert_eq!(text(WIFI_SECURITY, "psk"), "new-password");
+        assert!(!updated.contains_key("ipv4"));
+        assert_eq!(calls.activated.len(), 1);
+    }
+}
@@ -0,0 +1,246 @@
+use chrono::Local;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::AppHandle;
+use zbus::zvariant::OwnedFd;
+
+use crate::logind;
+
+// ===== Sleep and Idle Inhibition =====
+//
+// Keeps the machine awake through logind inhibitor locks. A lock is held for
+// as long as the file descriptor returned by `Inhibit` stays open, so dropping
+// the fd releases it. Besides the lock taken on request ("caffeine"), a sleep
+// lock is held automatically while a media player is playing. Changes are
+// published on the `inhibit` sampler topic.
+
+const WHO: &str = "SisCrystal";
+
+/// Lock types that can be requested.
+const INHIBIT_WHAT: &[&str] = &["idle", "sleep"];
+
+#[derive(Serialize, Clone, PartialEq)]
+pub struct InhibitLock {
+    /// e.g. ["idle", "sleep"]
+    pub what: Vec<String>,
+    pub reason: String,
+    /// Unix time in milliseconds
+    pub since: i64,
+    /// When the lock is released automatically, if a timer was set
+    pub expires_at: Option<i64>,
+}
+
+#[derive(Serialize, Clone, PartialEq)]
+pub struct InhibitStatus {
+    /// The lock taken through `take_inhibitor`
+    pub lock: Option<InhibitLock>,
+    /// Name of the player that keeps the machine awake while playing
+    pub media_player: Option<String>,
+    pub staying_awake: bool,
+}
+
+/// An inhibitor held by any application, as listed by logind.
+#[derive(Serialize, Clone)]
+pub struct Inhibitor {
+    pub what: Vec<String>,
+    pub who: String,
+    pub why: String,
+    /// "block" or "delay"
+    pub mode: String,
+    pub uid: u32,
+    pub pid: u32,
+}
+
+struct HeldLock {
+    info: InhibitLock,
+    // Identifies this lock to its release timer, so a timer never releases a
+    // lock that replaced it.
+    id: u64,
+    _fd: OwnedFd,
+}
+
+struct MediaLock {
+    player_id: String,
+    player: String,
+    _fd: OwnedFd,
+}
+
+static HELD: Mutex<Option<HeldLock>> = Mutex::new(None);
+static MEDIA_LOCK: Mutex<Option<MediaLock>> = Mutex::new(None);
+// Players that are playing right now, by bus name, with their display names.
+static PLAYING: Mutex<BTreeMap<String, String>> = Mutex::new(BTreeMap::new());
+static NEXT_ID: Mutex<u64> = Mutex::new(0);
+
+fn take_lock(what: &str, why: &str) -> Result<OwnedFd, String> {
+    let conn = crate::bus::system()?;
+    logind::manager(&conn)?
+        .inhibit(what, WHO, why, "block")
+        .map_err(|e| format!("Failed to take inhibitor lock: {e}"))
+}
+
+fn publish(app: &AppHandle) {
+    let status = serde_json::to_value(get_inhibit_status()).unwrap_or_default();
+    crate::sampler::publish(app, "inhibit", status);
+}
+
+#[tauri::command]
+pub fn get_inhibit_status() -> InhibitStatus {
+    let lock = HELD
+        .lock()
+        .ok()
+        .and_then(|held| held.as_ref().map(|h| h.info.clone()));
+    let media_player = MEDIA_LOCK
+        .lock()
+        .ok()
+        .and_then(|media| media.as_ref().map(|m| m.player.clone()));
+    InhibitStatus {
+        staying_awake: lock.is_some() || media_player.is_some(),
+        lock,
+        media_player,
+    }
+}
+
+/// Keeps the machine awake. `what` defaults to both "idle" and "sleep";
+/// with `minutes` the lock is released automatically. Replaces a lock taken
+/// earlier.
+#[tauri::command]
+pub fn take_inhibitor(
+    app: AppHandle,
+    what: Option<Vec<String>>,
+    reason: String,
+    minutes: Option<u32>,
+) -> Result<InhibitStatus, String> {
+    let what = what.unwrap_or_else(|| INHIBIT_WHAT.iter().map(|w| w.to_string()).collect());
+    if what.is_empty() {
+        return Err("Nothing to inhibit".to_string());
+    }
+    if let Some(unknown) = what.iter().find(|w| !INHIBIT_WHAT.contains(&w.as_str())) {
+        return Err(format!("Unknown inhibitor type: {unknown}"));
+    }
+    if minutes == Some(0) {
+        return Err("The timer must be at least one minute".to_string());
+    }
+    let reason = if reason.trim().is_empty() {
+        "Requested by the user".to_string()
+    } else {
+        reason
+    };
+
+    let fd = take_lock(&what.join(":"), &reason)?;
+    let since = Local::now().timestamp_millis();
+    let id = {
+        let mut next = NEXT_ID.lock().map_err(|e| e.to_string())?;
+        *next += 1;
+        *next
+    };
+    *HELD.lock().map_err(|e| e.to_string())? = Some(HeldLock {
+        info: InhibitLock {
+            what,
+            reason,
+            since,
+            expires_at: minutes.map(|m| since + i64::from(m) * 60_000),
+        },
+        id,
+        _fd: fd,
+    });
+
+    if let Some(minutes) = minutes {
+        let app = app.clone();
+        std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_secs(u64::from(minutes) * 60));
+            let expired = HELD
+                .lock()
+                .map(|mut held| {
+                    if held.as_ref().is_some_and(|h| h.id == id) {
+                        held.take();
+                        true
+                    } else {
+                        false
+                    }
+                })
+                .unwrap_or(false);
+            if expired {
+                publish(&app);
+            }
+        });
+    }
+
+    publish(&app);
+    Ok(get_inhibit_status())
+}
+
+#[tauri::command]
+pub fn release_inhibitor(app: AppHandle) -> Result<InhibitStatus, String> {
+    HELD.lock().map_err(|e| e.to_string())?.take();
+    publish(&app);
+    Ok(get_inhibit_status())
+}
+
+/// Inhibitor locks currently held by all applications, including ours.
+#[tauri::command]
+pub fn list_inhibitors() -> Result<Vec<Inhibitor>, String> {
+    let conn = crate::bus::system()?;
+    let entries = logind::manager(&conn)?
+        .list_inhibitors()
+        .map_err(|e| e.to_string())?;
+    Ok(entries
+        .into_iter()
+        .map(|(what, who, why, mode, uid, pid)| Inhibitor {
+            what: what.split(':').map(str::to_string).collect(),
+            who,
+            why,
+            mode,
+            uid,
+            pid,
+        })
+        .collect())
+}
+
+/// Holds a sleep lock while any media player is playing. Called by the media
+/// listener whenever a player's playback changes; `playing` is the player's
+/// name while it plays and `None` once it pauses, stops or quits.
+pub fn media_changed(app: &AppHandle, player_id: &str, playing: Option<&str>) {
+    let Ok(mut players) = PLAYING.lock() else {
+        return;
+    };
+    match playing {
+        Some(identity) => players.insert(player_id.to_string(), identity.to_string()),
+        None => players.remove(player_id),
+    };
+
+    let Ok(mut media) = MEDIA_LOCK.lock() else {
+        return;
+    };
+    // The player holding the lock keeps it for as long as it plays.
+    if let Some(held) = media.as_ref() {
+        if players.contains_key(&held.player_id) {
+            return;
+        }
+    }
+    let next = players
+        .iter()
+        .next()
+        .map(|(id, identity)| (id.clone(), identity.clone()));
+    drop(players);
+    if media.is_none() && next.is_none() {
+        return;
+    }
+    // Release the previous player's lock before taking the next one.
+    *media = None;
+    if let Some((player_id, player)) = next {
+        match take_lock("sleep", &format!("Playing media in {player}")) {
+            Ok(fd) => {
+                *media = Some(MediaLock {
+                    player_id,
+                    player,
+                    _fd: fd,
+                })
+            }
+            Err(e) => log::warn!("Media inhibitor: {e}"),
+        }
+    }
+    drop(media);
+    publish(app);
+}
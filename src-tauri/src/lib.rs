@@ -1,12 +1,32 @@
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
 use std::fs;
 use std::path::PathBuf;
 use std::process::Command;
 use std::sync::{Mutex, OnceLock};
 use sysinfo::{System, Disks};
 use walkdir::WalkDir;
-use mpris::PlayerFinder;
+
+mod audio;
+mod bluetooth;
+mod brightness;
+mod bus;
+mod connectivity;
+mod hotspot;
+mod inhibit;
+mod logind;
+mod media;
+mod network;
+mod night_light;
+mod nm;
+#[cfg(test)]
+mod nm_mock;
+mod power;
+mod power_alerts;
+mod power_profiles;
+mod power_schedule;
+mod sampler;
+mod traffic;
+mod upower;
 
 // ===== Type Definitions =====
 
@@ -28,14 +48,8 @@ pub struct BatteryInfo {
     pub is_charging: bool,
     pub time_to_full: Option<u64>,
     pub time_to_empty: Option<u64>,
-}
-
-#[derive(Serialize, Clone)]
-pub struct NetworkInfo {
-    pub is_connected: bool,
-    pub ssid: Option<String>,
-    pub signal_strength: Option<i32>,
-    pub ip_address: Option<String>,
+    /// Combined charge or discharge rate in watts
+    pub power: Option<f32>,
 }
 
 #[derive(Serialize, Clone)]
@@ -52,6 +66,8 @@ pub struct DiskInfo {
 pub struct AudioInfo {
     pub volume: u32,
     pub is_muted: bool,
+    pub mic_volume: Option<u32>,
+    pub is_mic_muted: bool,
     pub current_track: Option<String>,
     pub current_artist: Option<String>,
     pub is_playing: bool,
@@ -102,6 +118,33 @@ pub struct DesktopSettings {
     pub animation_speed: f32,
     pub blur_enabled: bool,
     pub transparency: f32,
+    /// MPRIS bus name of the player media commands target by default
+    pub media_player: Option<String>,
+    /// Battery charge thresholds in percent, restored at startup
+    pub charge_start_threshold: Option<u8>,
+    pub charge_end_threshold: Option<u8>,
+    /// Battery percentages that trigger a low-battery notification
+    pub battery_warning_levels: Vec<u8>,
+    /// Percentage at which `battery_critical_action` runs
+    pub battery_critical_level: u8,
+    /// "none", "suspend", "hibernate" or "shutdown"
+    pub battery_critical_action: String,
+    /// Notify when the charger is plugged in or out
+    pub power_notifications: bool,
+    /// Night light fields are read-only through `save_settings`, which keeps
+    /// the saved values; change them with `set_night_light` and
+    /// `set_night_light_schedule`.
+    pub night_light_enabled: bool,
+    /// Color temperature in Kelvin while night light is on
+    pub night_light_temperature: u32,
+    /// "manual", "fixed" (from/to) or "sunset" (sunset to sunrise)
+    pub night_light_schedule: String,
+    /// Hours of the day for the fixed schedule, e.g. 20.5 for 20:30
+    pub night_light_from: f64,
+    pub night_light_to: f64,
+    /// Location used to compute sunset and sunrise
+    pub night_light_latitude: Option<f64>,
+    pub night_light_longitude: Option<f64>,
 }
 
 impl Default for DesktopSettings {
@@ -123,6 +166,20 @@ impl Default for DesktopSettings {
             animation_speed: 1.0,
             blur_enabled: true,
             transparency: 0.8,
+            media_player: None,
+            charge_start_threshold: None,
+            charge_end_threshold: None,
+            battery_warning_levels: vec![20, 10, 5],
+            battery_critical_level: 3,
+            battery_critical_action: String::from("suspend"),
+            power_notifications: true,
+            night_light_enabled: false,
+            night_light_temperature: 4000,
+            night_light_schedule: String::from("manual"),
+            night_light_from: 20.0,
+            night_light_to: 7.0,
+            night_light_latitude: None,
+            night_light_longitude: None,
         }
     }
 }
@@ -142,11 +199,23 @@ fn get_config_dir() -> PathBuf {
     PathBuf::from(".sis-crystal")
 }
 
+fn get_cache_dir() -> PathBuf {
+    if let Some(dir) = dirs::cache_dir() {
+        return dir.join("sis-crystal");
+    }
+
+    if let Some(home) = dirs::home_dir() {
+        return home.join(".cache").join("sis-crystal");
+    }
+
+    std::env::temp_dir().join("sis-crystal")
+}
+
 fn get_settings_path() -> PathBuf {
     get_config_dir().join("settings.json")
 }
 
-fn run_command(cmd: &str, args: &[&str]) -> Result<String, String> {
+pub(crate) fn run_command(cmd: &str, args: &[&str]) -> Result<String, String> {
     Command::new(cmd)
         .args(args)
         .output()
@@ -160,27 +229,6 @@ fn run_command(cmd: &str, args: &[&str]) -> Result<String, String> {
         })
 }
 
-fn parse_first_percent_value(s: &str) -> Option<u32> {
-    // Find the first "NN%" occurrence and extract NN.
-    let bytes = s.as_bytes();
-    for (i, b) in bytes.iter().enumerate() {
-        if *b != b'%' || i == 0 {
-            continue;
-        }
-        let mut j = i;
-        while j > 0 && bytes[j - 1].is_ascii_digit() {
-            j -= 1;
-        }
-        if j == i {
-            continue;
-        }
-        if let Ok(v) = s[j..i].parse::<u32>() {
-            return Some(v);
-        }
-    }
-    None
-}
-
 fn parse_wpctl_volume(output: &str) -> Option<(u32, bool)> {
     // Example: "Volume: 0.52" or "Volume: 0.52 [MUTED]"
     let muted = output.to_uppercase().contains("MUTED");
@@ -299,91 +347,6 @@ fn resolve_icon_path(icon: &str) -> Option<String> {
     None
 }
 
-fn get_player_metadata() -> (Option<String>, Option<String>, bool) {
-    // Primary: MPRIS over DBus (works on GNOME when a player exposes MPRIS)
-    if let Ok(finder) = PlayerFinder::new() {
-        if let Ok(players) = finder.find_all() {
-            // Prefer a playing player, else the first one
-            let mut chosen = None;
-            for p in players {
-                if let Ok(status) = p.get_playback_status() {
-                    if status == mpris::PlaybackStatus::Playing {
-                        chosen = Some(p);
-                        break;
-                    }
-                }
-                if chosen.is_none() {
-                    chosen = Some(p);
-                }
-            }
-
-            if let Some(player) = chosen {
-                let playing = player
-                    .get_playback_status()
-                    .map(|s| s == mpris::PlaybackStatus::Playing)
-                    .unwrap_or(false);
-                let meta = player.get_metadata().ok();
-                let title = meta
-                    .as_ref()
-                    .and_then(|m| m.title().map(|s| s.to_string()));
-                let artist = meta
-                    .as_ref()
-                    .and_then(|m| m.artists())
-                    .and_then(|a| a.get(0).map(|s| s.to_string()));
-                // Always return playback state if a player is present.
-                // Some players expose playback status but omit metadata.
-                if title.is_some() || artist.is_some() {
-                    return (title, artist, playing);
-                }
-
-                // If MPRIS is present but metadata is empty, try playerctl to fill title/artist.
-                if let Ok(output) = run_command(
-                    "playerctl",
-                    &[
-                        "metadata",
-                        "--player=%any",
-                        "--format",
-                        "{{title}}|||{{artist}}|||{{status}}",
-                    ],
-                ) {
-                    let parts: Vec<&str> = output.trim().split("|||").collect();
-                    let title = parts
-                        .get(0)
-                        .filter(|s| !s.is_empty())
-                        .map(|s| s.to_string());
-                    let artist = parts
-                        .get(1)
-                        .filter(|s| !s.is_empty())
-                        .map(|s| s.to_string());
-                    let playing_from_playerctl = parts.get(2).map(|s| *s == "Playing").unwrap_or(false);
-                    return (title, artist, playing || playing_from_playerctl);
-                }
-
-                return (None, None, playing);
-            }
-        }
-    }
-
-    // Fallback: playerctl if installed
-    if let Ok(output) = run_command(
-        "playerctl",
-        &[
-            "metadata",
-            "--player=%any",
-            "--format",
-            "{{title}}|||{{artist}}|||{{status}}",
-        ],
-    ) {
-        let parts: Vec<&str> = output.trim().split("|||").collect();
-        let title = parts.get(0).filter(|s| !s.is_empty()).map(|s| s.to_string());
-        let artist = parts.get(1).filter(|s| !s.is_empty()).map(|s| s.to_string());
-        let playing = parts.get(2).map(|s| *s == "Playing").unwrap_or(false);
-        return (title, artist, playing);
-    }
-
-    (None, None, false)
-}
-
 fn get_file_icon(name: &str, is_dir: bool) -> String {
     if is_dir {
         return "folder".to_string();
@@ -509,80 +472,6 @@ fn get_system_info() -> SystemInfo {
     info
 }
 
-#[tauri::command]
-fn get_battery_info() -> Option<BatteryInfo> {
-    use battery::Manager;
-    
-    let manager = Manager::new().ok()?;
-    let battery = manager.batteries().ok()?.next()?.ok()?;
-    
-    Some(BatteryInfo {
-        percentage: battery.state_of_charge().value * 100.0,
-        is_charging: battery.state() == battery::State::Charging,
-        time_to_full: battery.time_to_full().map(|t| t.value as u64),
-        time_to_empty: battery.time_to_empty().map(|t| t.value as u64),
-    })
-}
-
-#[tauri::command]
-fn get_network_info() -> NetworkInfo {
-    static LAST_NETWORK_REFRESH: OnceLock<Mutex<(std::time::Instant, NetworkInfo)>> = OnceLock::new();
-    static NETWORK_CACHE_DURATION: std::time::Duration = std::time::Duration::from_secs(3);
-
-    if let Ok(cache_guard) = LAST_NETWORK_REFRESH.get_or_init(|| {
-        let ssid = run_command("nmcli", &["-t", "-f", "active,ssid", "dev", "wifi"])
-            .ok()
-            .and_then(|output| {
-                output.lines()
-                    .find(|line| line.starts_with("yes:"))
-                    .map(|line| line.trim_start_matches("yes:").to_string())
-            });
-        
-        let ip_address = run_command("hostname", &["-I"])
-            .ok()
-            .and_then(|output| output.split_whitespace().next().map(String::from));
-
-        let info = NetworkInfo {
-            is_connected: ssid.is_some(),
-            ssid,
-            signal_strength: None,
-            ip_address,
-        };
-        Mutex::new((std::time::Instant::now(), info))
-    }).lock() {
-        let (last_refresh, cached_info) = cache_guard.clone();
-        if last_refresh.elapsed() < NETWORK_CACHE_DURATION {
-            return cached_info;
-        }
-    }
-
-    let ssid = run_command("nmcli", &["-t", "-f", "active,ssid", "dev", "wifi"])
-        .ok()
-        .and_then(|output| {
-            output.lines()
-                .find(|line| line.starts_with("yes:"))
-                .map(|line| line.trim_start_matches("yes:").to_string())
-        });
-    
-    let ip_address = run_command("hostname", &["-I"])
-        .ok()
-        .and_then(|output| output.split_whitespace().next().map(String::from));
-
-    let info = NetworkInfo {
-        is_connected: ssid.is_some(),
-        ssid,
-        signal_strength: None,
-        ip_address,
-    };
-
-    if let Ok(mut cache_guard) = LAST_NETWORK_REFRESH.get_or_init(|| Mutex::new((std::time::Instant::now(), info.clone()))).lock() {
-        cache_guard.0 = std::time::Instant::now();
-        cache_guard.1 = info.clone();
-    }
-
-    info
-}
-
 #[tauri::command]
 fn get_user_profile() -> UserProfile {
     let username = whoami::username();
@@ -612,23 +501,31 @@ fn get_disk_info() -> Vec<DiskInfo> {
 
 #[tauri::command]
 fn get_audio_info() -> AudioInfo {
-    let (volume, is_muted) = if let Ok(output) = run_command("pactl", &["get-sink-volume", "@DEFAULT_SINK@"]) {
-        let v = parse_first_percent_value(&output).unwrap_or(50);
-        let muted = run_command("pactl", &["get-sink-mute", "@DEFAULT_SINK@"]) 
-            .map(|s| s.contains("yes"))
-            .unwrap_or(false);
-        (v, muted)
+    let (volume, is_muted) = if let Ok(sink) = audio::default_device("sink") {
+        (sink.volume, sink.is_muted)
     } else if let Ok(output) = run_command("wpctl", &["get-volume", "@DEFAULT_AUDIO_SINK@"]) {
         parse_wpctl_volume(&output).unwrap_or((50, false))
     } else {
         (50, false)
     };
 
-    let (current_track, current_artist, is_playing) = get_player_metadata();
+    let (mic_volume, is_mic_muted) = if let Ok(source) = audio::default_device("source") {
+        (Some(source.volume), source.is_muted)
+    } else if let Ok(output) = run_command("wpctl", &["get-volume", "@DEFAULT_AUDIO_SOURCE@"]) {
+        parse_wpctl_volume(&output)
+            .map(|(v, m)| (Some(v), m))
+            .unwrap_or((None, false))
+    } else {
+        (None, false)
+    };
+
+    let (current_track, current_artist, is_playing) = media::get_player_metadata();
 
     AudioInfo {
         volume,
         is_muted,
+        mic_volume,
+        is_mic_muted,
         current_track,
         current_artist,
         is_playing,
@@ -638,7 +535,7 @@ fn get_audio_info() -> AudioInfo {
 #[tauri::command]
 fn set_volume(volume: u32) -> Result<(), String> {
     let v = volume.min(150);
-    if run_command("pactl", &["set-sink-volume", "@DEFAULT_SINK@", &format!("{}%", v)]).is_ok() {
+    if audio::set_audio_device_volume("sink", "@DEFAULT_SINK@", v).is_ok() {
         return Ok(());
     }
     // PipeWire
@@ -648,7 +545,7 @@ fn set_volume(volume: u32) -> Result<(), String> {
 
 #[tauri::command]
 fn toggle_mute() -> Result<(), String> {
-    if run_command("pactl", &["set-sink-mute", "@DEFAULT_SINK@", "toggle"]).is_ok() {
+    if audio::pactl_set(&["set-sink-mute", "@DEFAULT_SINK@", "toggle"]).is_ok() {
         return Ok(());
     }
     run_command("wpctl", &["set-mute", "@DEFAULT_AUDIO_SINK@", "toggle"])?;
@@ -656,81 +553,14 @@ fn toggle_mute() -> Result<(), String> {
 }
 
 #[tauri::command]
-fn media_control(action: &str) -> Result<(), String> {
-    let cmd = match action {
-        "play" | "pause" => "play-pause",
-        "next" => "next",
-        "previous" => "previous",
-        _ => return Err("Unknown action".to_string()),
-    };
-
-    // Primary: MPRIS
-    let mut mpris_error: Option<String> = None;
-    if let Ok(finder) = PlayerFinder::new() {
-        if let Ok(players) = finder.find_all() {
-            // Prefer playing player
-            let mut chosen = None;
-            for p in players {
-                if let Ok(status) = p.get_playback_status() {
-                    if status == mpris::PlaybackStatus::Playing {
-                        chosen = Some(p);
-                        break;
-                    }
-                }
-                if chosen.is_none() {
-                    chosen = Some(p);
-                }
-            }
-
-            if let Some(player) = chosen {
-                let res: Result<(), String> = match cmd {
-                    "play-pause" => player.play_pause().map_err(|e| e.to_string()),
-                    "next" => player.next().map_err(|e| e.to_string()),
-                    "previous" => player.previous().map_err(|e| e.to_string()),
-                    _ => Ok(()),
-                };
-                if res.is_ok() {
-                    return Ok(());
-                }
-                mpris_error = res.err();
-            }
-        }
+fn toggle_mic_mute() -> Result<(), String> {
+    if audio::pactl_set(&["set-source-mute", "@DEFAULT_SOURCE@", "toggle"]).is_ok() {
+        return Ok(());
     }
-
-    // Fallback: playerctl
-    run_command("playerctl", &["--player=%any", cmd]).map_err(|e| {
-        if let Some(mpris_error) = &mpris_error {
-            format!(
-                "MPRIS failed ({mpris_error}); playerctl also failed ({e})."
-            )
-        } else {
-            format!(
-                "No MPRIS player found. Install 'playerctl' or run a MPRIS-compatible player. Details: {e}"
-            )
-        }
-    })?;
-    Ok(())
-}
-
-#[tauri::command]
-fn set_brightness(brightness: u32) -> Result<(), String> {
-    run_command("brightnessctl", &["set", &format!("{}%", brightness.min(100))])?;
+    run_command("wpctl", &["set-mute", "@DEFAULT_AUDIO_SOURCE@", "toggle"])?;
     Ok(())
 }
 
-#[tauri::command]
-fn get_brightness() -> u32 {
-    run_command("brightnessctl", &["get"])
-        .ok()
-        .and_then(|current| {
-            let max = run_command("brightnessctl", &["max"]).ok()?;
-            let c: f32 = current.trim().parse().ok()?;
-            let m: f32 = max.trim().parse().ok()?;
-            Some((c / m * 100.0) as u32)
-        })
-        .unwrap_or(100)
-}
-
 #[tauri::command]
 fn get_installed_apps() -> Vec<DesktopApp> {
     let mut apps = Vec::new();
@@ -966,65 +796,25 @@ fn get_settings() -> DesktopSettings {
     load_settings()
 }
 
-#[tauri::command]
-fn save_settings(settings: DesktopSettings) -> Result<(), String> {
-    save_settings_internal(&settings)
-}
-
-#[tauri::command]
-fn get_wifi_networks() -> Vec<HashMap<String, String>> {
-    run_command("nmcli", &["-t", "-f", "SSID,SIGNAL,SECURITY", "dev", "wifi", "list"])
-        .map(|output| {
-            output.lines()
-                .filter(|line| !line.is_empty())
-                .map(|line| {
-                    let parts: Vec<&str> = line.split(':').collect();
-                    let mut map = HashMap::new();
-                    if let Some(ssid) = parts.get(0) { map.insert("ssid".to_string(), ssid.to_string()); }
-                    if let Some(signal) = parts.get(1) { map.insert("signal".to_string(), signal.to_string()); }
-                    if let Some(security) = parts.get(2) { map.insert("security".to_string(), security.to_string()); }
-                    map
-                })
-                .collect()
-        })
-        .unwrap_or_default()
+/// Fields only changed through their own commands. The UI saves the whole
+/// object it loaded earlier, so these are taken from disk instead of from it.
+fn keep_backend_fields(settings: &mut DesktopSettings, current: DesktopSettings) {
+    settings.media_player = current.media_player;
+    settings.charge_start_threshold = current.charge_start_threshold;
+    settings.charge_end_threshold = current.charge_end_threshold;
+    settings.night_light_enabled = current.night_light_enabled;
+    settings.night_light_temperature = current.night_light_temperature;
+    settings.night_light_schedule = current.night_light_schedule;
+    settings.night_light_from = current.night_light_from;
+    settings.night_light_to = current.night_light_to;
+    settings.night_light_latitude = current.night_light_latitude;
+    settings.night_light_longitude = current.night_light_longitude;
 }
 
 #[tauri::command]
-fn connect_wifi(ssid: &str, password: &str) -> Result<(), String> {
-    if password.is_empty() {
-        run_command("nmcli", &["dev", "wifi", "connect", ssid])?;
-    } else {
-        run_command("nmcli", &["dev", "wifi", "connect", ssid, "password", password])?;
-    }
-    Ok(())
-}
-
-#[tauri::command]
-fn disconnect_wifi() -> Result<(), String> {
-    run_command("nmcli", &["dev", "disconnect", "wlan0"])?;
-    Ok(())
-}
-
-#[tauri::command]
-fn get_bluetooth_devices() -> Vec<HashMap<String, String>> {
-    run_command("bluetoothctl", &["devices"])
-        .map(|output| {
-            output.lines()
-                .filter_map(|line| {
-                    let parts: Vec<&str> = line.split_whitespace().collect();
-                    if parts.len() >= 3 {
-                        let mut map = HashMap::new();
-                        map.insert("mac".to_string(), parts[1].to_string());
-                        map.insert("name".to_string(), parts[2..].join(" "));
-                        Some(map)
-                    } else {
-                        None
-                    }
-                })
-                .collect()
-        })
-        .unwrap_or_default()
+fn save_settings(mut settings: DesktopSettings) -> Result<(), String> {
+    keep_backend_fields(&mut settings, load_settings());
+    save_settings_internal(&settings)
 }
 
 #[tauri::command]
@@ -1033,6 +823,7 @@ fn system_action(action: &str) -> Result<(), String> {
         "shutdown" => { run_command("systemctl", &["poweroff"])?; }
         "reboot" => { run_command("systemctl", &["reboot"])?; }
         "suspend" => { run_command("systemctl", &["suspend"])?; }
+        "hibernate" => { run_command("systemctl", &["hibernate"])?; }
         "lock" => { 
             let _ = run_command("loginctl", &["lock-session"]);
             let _ = run_command("gnome-screensaver-command", &["-l"]);
@@ -1082,23 +873,60 @@ fn read_file(path: &str) -> Result<String, String> {
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
+        .plugin(
+            tauri_plugin_log::Builder::new()
+                .level(log::LevelFilter::Info)
+                .build(),
+        )
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_notification::init())
         .plugin(tauri_plugin_process::init())
+        .setup(|app| {
+            sampler::start(app.handle());
+            traffic::start();
+            bluetooth::start(app.handle());
+            power_profiles::start(app.handle());
+            power::restore_charge_thresholds();
+            power_alerts::start(app.handle());
+            night_light::start();
+            power_schedule::start(app.handle());
+            media::start_listener(app.handle());
+            Ok(())
+        })
+        .on_window_event(|window, event| {
+            if let tauri::WindowEvent::Destroyed = event {
+                sampler::forget_window(window.label());
+            }
+        })
         .invoke_handler(tauri::generate_handler![
             get_system_info,
-            get_battery_info,
-            get_network_info,
+            power::get_battery_info,
+            power::get_charge_thresholds,
+            power::set_charge_thresholds,
+            power_profiles::get_power_profiles,
+            power_profiles::set_power_profile,
+            power_schedule::list_scheduled_actions,
+            power_schedule::schedule_power_action,
+            power_schedule::cancel_scheduled_action,
             get_disk_info,
             get_audio_info,
             set_volume,
             toggle_mute,
-            media_control,
-            set_brightness,
-            get_brightness,
+            toggle_mic_mute,
+            brightness::set_brightness,
+            brightness::get_brightness,
+            brightness::list_brightness_devices,
+            brightness::set_device_brightness,
+            night_light::get_night_light,
+            night_light::set_night_light,
+            night_light::set_night_light_schedule,
+            inhibit::get_inhibit_status,
+            inhibit::take_inhibitor,
+            inhibit::release_inhibitor,
+            inhibit::list_inhibitors,
             get_installed_apps,
             launch_app,
             get_directory_contents,
@@ -1107,15 +935,75 @@ pub fn run() {
             set_wallpaper,
             get_settings,
             save_settings,
-            get_wifi_networks,
-            connect_wifi,
-            disconnect_wifi,
-            get_bluetooth_devices,
+            bluetooth::list_bluetooth_adapters,
+            bluetooth::get_bluetooth_devices,
+            bluetooth::set_bluetooth_powered,
+            bluetooth::start_bluetooth_discovery,
+            bluetooth::stop_bluetooth_discovery,
+            bluetooth::pair_bluetooth_device,
+            bluetooth::connect_bluetooth_device,
+            bluetooth::disconnect_bluetooth_device,
+            bluetooth::set_bluetooth_trusted,
+            bluetooth::remove_bluetooth_device,
+            bluetooth::bluetooth_agent_reply,
             get_user_profile,
             system_action,
             run_shell,
             read_file,
+            audio::list_audio_devices,
+            audio::set_default_audio_device,
+            audio::set_audio_device_volume,
+            audio::set_audio_device_mute,
+            audio::set_audio_device_port,
+            audio::list_audio_streams,
+            audio::set_audio_stream_volume,
+            audio::set_audio_stream_mute,
+            audio::move_audio_stream,
+            audio::start_mic_level_meter,
+            audio::stop_mic_level_meter,
+            media::list_media_players,
+            media::select_media_player,
+            media::media_control,
+            media::get_media_state,
+            media::media_seek,
+            media::media_set_position,
+            media::media_set_shuffle,
+            media::media_set_loop,
+            network::get_network_info,
+            network::list_network_devices,
+            network::list_active_connections,
+            network::get_wifi_networks,
+            network::connect_wifi,
+            network::disconnect_wifi,
+            network::list_wifi_devices,
+            network::get_wifi_radio,
+            network::set_wifi_enabled,
+            network::request_wifi_scan,
+            network::get_wifi_scan_status,
+            network::connect_hidden_wifi,
+            network::list_saved_connections,
+            network::forget_connection,
+            network::set_connection_autoconnect,
+            network::set_connection_ipv4,
+            network::set_connection_ipv6,
+            hotspot::start_hotspot,
+            hotspot::stop_hotspot,
+            hotspot::get_hotspot_status,
+            hotspot::get_wifi_share_code,
+            connectivity::get_connectivity,
+            connectivity::check_connectivity,
+            traffic::get_network_traffic_history,
+            traffic::get_data_usage,
+            sampler::list_event_topics,
+            sampler::subscribe_events,
+            sampler::unsubscribe_events,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|_app, event| {
+            if let tauri::RunEvent::Exit = event {
+                traffic::flush();
+                night_light::stop();
+            }
+        });
 }
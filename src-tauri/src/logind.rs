@@ -0,0 +1,46 @@
+use zbus::blocking::Connection;
+
+// ===== logind D-Bus Client =====
+//
+// Typed proxies for systemd-logind. The session object is addressed as
+// "session/auto", which logind resolves to the session of the calling process.
+
+/// One entry of `ListInhibitors`: what, who, why, mode, uid, pid.
+pub type InhibitorEntry = (String, String, String, String, u32, u32);
+
+#[zbus::proxy(
+    interface = "org.freedesktop.login1.Manager",
+    default_service = "org.freedesktop.login1",
+    default_path = "/org/freedesktop/login1"
+)]
+pub trait Manager {
+    /// Takes an inhibitor lock; it is held until the returned fd is closed.
+    fn inhibit(
+        &self,
+        what: &str,
+        who: &str,
+        why: &str,
+        mode: &str,
+    ) -> zbus::Result<zbus::zvariant::OwnedFd>;
+
+    fn list_inhibitors(&self) -> zbus::Result<Vec<InhibitorEntry>>;
+}
+
+#[zbus::proxy(
+    interface = "org.freedesktop.login1.Session",
+    default_service = "org.freedesktop.login1",
+    default_path = "/org/freedesktop/login1/session/auto"
+)]
+pub trait Session {
+    /// Writes a backlight or LED brightness without root, for devices of the
+    /// session's seat.
+    fn set_brightness(&self, subsystem: &str, name: &str, brightness: u32) -> zbus::Result<()>;
+}
+
+pub fn manager(conn: &Connection) -> Result<ManagerProxyBlocking<'static>, String> {
+    ManagerProxyBlocking::new(conn).map_err(|e| e.to_string())
+}
+
+pub fn session(conn: &Connection) -> Result<SessionProxyBlocking<'static>, String> {
+    SessionProxyBlocking::new(conn).map_err(|e| e.to_string())
+}
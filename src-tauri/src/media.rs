@@ -0,0 +1,682 @@
+use mpris::{Event, LoopStatus, Metadata, PlaybackStatus, Player, PlayerFinder};
+use serde::Serialize;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, SystemTime};
+use tauri::{AppHandle, Emitter};
+
+use crate::{get_cache_dir, load_settings, run_command, save_settings_internal};
+
+// ===== Media Players (MPRIS) =====
+//
+// Players are identified by their D-Bus bus name
+// (e.g. "org.mpris.MediaPlayer2.spotify"). Commands take an optional player
+// id; without one, the player selected in settings is used, falling back to
+// the first playing player and then to the first player found.
+
+const MPRIS_PREFIX: &str = "org.mpris.MediaPlayer2.";
+
+#[derive(Serialize, Clone, PartialEq)]
+pub struct MediaPlayerInfo {
+    pub id: String,
+    pub identity: String,
+    pub status: String,
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub is_selected: bool,
+}
+
+/// Full now-playing state of one player.
+#[derive(Serialize, Clone, PartialEq)]
+pub struct MediaState {
+    pub player_id: String,
+    pub identity: String,
+    pub status: String,
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    /// Art URL as reported by the player
+    pub art_url: Option<String>,
+    /// Local copy of the artwork under the cache dir, loadable by the UI
+    pub art_path: Option<String>,
+    pub length_ms: Option<u64>,
+    pub position_ms: Option<u64>,
+    pub rate: f64,
+    pub shuffle: Option<bool>,
+    /// "None", "Track" or "Playlist"
+    pub loop_status: Option<String>,
+    pub can_seek: bool,
+}
+
+pub(crate) fn status_name(status: PlaybackStatus) -> &'static str {
+    match status {
+        PlaybackStatus::Playing => "Playing",
+        PlaybackStatus::Paused => "Paused",
+        PlaybackStatus::Stopped => "Stopped",
+    }
+}
+
+/// Name playerctl uses for a player ("spotify" for "org.mpris.MediaPlayer2.spotify").
+fn playerctl_name(id: &str) -> &str {
+    id.strip_prefix(MPRIS_PREFIX).unwrap_or(id)
+}
+
+fn find_players() -> Vec<Player> {
+    PlayerFinder::new()
+        .ok()
+        .and_then(|finder| finder.find_all().ok())
+        .unwrap_or_default()
+}
+
+fn is_playing(player: &Player) -> bool {
+    player
+        .get_playback_status()
+        .map(|s| s == PlaybackStatus::Playing)
+        .unwrap_or(false)
+}
+
+// The player picked with `select_media_player`, loaded from settings once.
+// Settings only change it through that command.
+static SELECTED_PLAYER: OnceLock<Mutex<Option<String>>> = OnceLock::new();
+
+fn selected_player() -> &'static Mutex<Option<String>> {
+    SELECTED_PLAYER.get_or_init(|| Mutex::new(load_settings().media_player))
+}
+
+fn remembered_player() -> Option<String> {
+    selected_player().lock().ok()?.clone()
+}
+
+/// Picks a player from `players` following the selection order described above.
+fn choose_player(players: Vec<Player>, requested: Option<&str>) -> Option<Player> {
+    if let Some(id) = requested {
+        return players.into_iter().find(|p| p.bus_name() == id);
+    }
+
+    let remembered = remembered_player();
+    let mut playing = None;
+    let mut first = None;
+    for p in players {
+        if remembered.as_deref() == Some(p.bus_name()) {
+            return Some(p);
+        }
+        if playing.is_none() && is_playing(&p) {
+            playing = Some(p);
+        } else if first.is_none() {
+            first = Some(p);
+        }
+    }
+    playing.or(first)
+}
+
+pub(crate) fn find_player(requested: Option<&str>) -> Result<Player, String> {
+    choose_player(find_players(), requested).ok_or_else(|| match requested {
+        Some(id) => format!("Media player not found: {id}"),
+        None => "No MPRIS player found".to_string(),
+    })
+}
+
+fn playerctl_metadata(player: Option<&str>) -> Option<(Option<String>, Option<String>, bool)> {
+    let player_arg = format!("--player={}", player.map(playerctl_name).unwrap_or("%any"));
+    let output = run_command(
+        "playerctl",
+        &[
+            player_arg.as_str(),
+            "metadata",
+            "--format",
+            "{{title}}|||{{artist}}|||{{status}}",
+        ],
+    )
+    .ok()?;
+    let parts: Vec<&str> = output.trim().split("|||").collect();
+    let title = parts
+        .first()
+        .filter(|s| !s.is_empty())
+        .map(|s| s.to_string());
+    let artist = parts
+        .get(1)
+        .filter(|s| !s.is_empty())
+        .map(|s| s.to_string());
+    let playing = parts.get(2).map(|s| *s == "Playing").unwrap_or(false);
+    Some((title, artist, playing))
+}
+
+pub(crate) fn get_player_metadata() -> (Option<String>, Option<String>, bool) {
+    // Primary: MPRIS over DBus (works on GNOME when a player exposes MPRIS)
+    if let Ok(player) = find_player(None) {
+        let playing = is_playing(&player);
+        let meta = player.get_metadata().ok();
+        let title = meta.as_ref().and_then(|m| m.title().map(|s| s.to_string()));
+        let artist = meta
+            .as_ref()
+            .and_then(|m| m.artists())
+            .and_then(|a| a.first().map(|s| s.to_string()));
+        // Always return playback state if a player is present.
+        // Some players expose playback status but omit metadata.
+        if title.is_some() || artist.is_some() {
+            return (title, artist, playing);
+        }
+
+        // If MPRIS is present but metadata is empty, try playerctl to fill title/artist.
+        if let Some((title, artist, playing_from_playerctl)) =
+            playerctl_metadata(Some(player.bus_name()))
+        {
+            return (title, artist, playing || playing_from_playerctl);
+        }
+
+        return (None, None, playing);
+    }
+
+    // Fallback: playerctl if installed
+    playerctl_metadata(None).unwrap_or((None, None, false))
+}
+
+#[tauri::command]
+pub fn list_media_players() -> Vec<MediaPlayerInfo> {
+    let remembered = remembered_player();
+    find_players()
+        .iter()
+        .map(|p| {
+            let meta = p.get_metadata().ok();
+            MediaPlayerInfo {
+                id: p.bus_name().to_string(),
+                identity: p.identity().to_string(),
+                status: p
+                    .get_playback_status()
+                    .map(status_name)
+                    .unwrap_or("Stopped")
+                    .to_string(),
+                title: meta.as_ref().and_then(|m| m.title().map(|s| s.to_string())),
+                artist: meta
+                    .as_ref()
+                    .and_then(|m| m.artists())
+                    .and_then(|a| a.first().map(|s| s.to_string())),
+                is_selected: remembered.as_deref() == Some(p.bus_name()),
+            }
+        })
+        .collect()
+}
+
+/// Remembers the player media commands should target. `None` returns to
+/// automatic selection.
+#[tauri::command]
+pub fn select_media_player(player: Option<String>) -> Result<(), String> {
+    let mut selected = selected_player().lock().map_err(|e| e.to_string())?;
+    let mut settings = load_settings();
+    settings.media_player = player.clone();
+    save_settings_internal(&settings)?;
+    *selected = player;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn media_control(action: &str, player: Option<String>) -> Result<(), String> {
+    let cmd = match action {
+        "play" | "pause" => "play-pause",
+        "next" => "next",
+        "previous" => "previous",
+        "stop" => "stop",
+        _ => return Err("Unknown action".to_string()),
+    };
+
+    // Primary: MPRIS
+    let mut mpris_error: Option<String> = None;
+    match find_player(player.as_deref()) {
+        Ok(p) => {
+            let res: Result<(), String> = match cmd {
+                "play-pause" => p.play_pause().map_err(|e| e.to_string()),
+                "next" => p.next().map_err(|e| e.to_string()),
+                "previous" => p.previous().map_err(|e| e.to_string()),
+                "stop" => p.stop().map_err(|e| e.to_string()),
+                _ => Ok(()),
+            };
+            if res.is_ok() {
+                return Ok(());
+            }
+            mpris_error = res.err();
+        }
+        // An explicitly requested player that does not exist is an error of its own.
+        Err(e) if player.is_some() => return Err(e),
+        Err(_) => {}
+    }
+
+    // Fallback: playerctl
+    let player_arg = format!(
+        "--player={}",
+        player.as_deref().map(playerctl_name).unwrap_or("%any")
+    );
+    run_command("playerctl", &[player_arg.as_str(), cmd]).map_err(|e| {
+        if let Some(mpris_error) = &mpris_error {
+            format!("MPRIS failed ({mpris_error}); playerctl also failed ({e}).")
+        } else {
+            format!(
+                "No MPRIS player found. Install 'playerctl' or run a MPRIS-compatible player. Details: {e}"
+            )
+        }
+    })?;
+    Ok(())
+}
+
+// ===== Media State & Artwork =====
+
+fn loop_status_name(status: LoopStatus) -> &'static str {
+    match status {
+        LoopStatus::None => "None",
+        LoopStatus::Track => "Track",
+        LoopStatus::Playlist => "Playlist",
+    }
+}
+
+/// Decodes %XX escapes in a file:// URI path.
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap_or("");
+            if let Ok(v) = u8::from_str_radix(hex, 16) {
+                out.push(v);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).to_string()
+}
+
+fn art_cache_dir() -> PathBuf {
+    get_cache_dir().join("media-art")
+}
+
+// Least recently used artwork is removed beyond this size.
+const ART_CACHE_MAX_BYTES: u64 = 32 * 1024 * 1024;
+// Leftover partial files from an interrupted copy or download.
+const ART_PARTIAL_MAX_AGE: Duration = Duration::from_secs(600);
+
+static ART_DOWNLOADS: OnceLock<Mutex<HashSet<PathBuf>>> = OnceLock::new();
+static ART_TEMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+// Set by `start_listener`, so finished downloads can refresh the UI.
+static MEDIA_APP: OnceLock<AppHandle> = OnceLock::new();
+
+fn art_downloads() -> &'static Mutex<HashSet<PathBuf>> {
+    ART_DOWNLOADS.get_or_init(|| Mutex::new(HashSet::new()))
+}
+
+/// Cache path for `url`. Local files are keyed on their mtime as well, since
+/// players often rewrite the same path with the next track's artwork.
+fn art_target(url: &str) -> Option<PathBuf> {
+    let mut hasher = DefaultHasher::new();
+    url.hash(&mut hasher);
+    if let Some(path) = url.strip_prefix("file://") {
+        let modified = fs::metadata(percent_decode(path))
+            .and_then(|m| m.modified())
+            .ok()?;
+        modified.hash(&mut hasher);
+    }
+    Some(art_cache_dir().join(format!("{:016x}", hasher.finish())))
+}
+
+fn art_temp_path(target: &Path) -> PathBuf {
+    let n = ART_TEMP_COUNTER.fetch_add(1, Ordering::Relaxed);
+    target.with_extension(format!("{}-{n}.part", std::process::id()))
+}
+
+/// Removes the least recently used artwork once the cache exceeds its cap.
+fn prune_art_cache() {
+    let Ok(entries) = fs::read_dir(art_cache_dir()) else {
+        return;
+    };
+    let now = SystemTime::now();
+    let mut files: Vec<(SystemTime, u64, PathBuf)> = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Some((modified, len)) = entry
+            .metadata()
+            .ok()
+            .and_then(|m| Some((m.modified().ok()?, m.len())))
+        else {
+            continue;
+        };
+        if path.extension().is_some() {
+            let stale = now
+                .duration_since(modified)
+                .is_ok_and(|age| age > ART_PARTIAL_MAX_AGE);
+            if stale {
+                let _ = fs::remove_file(&path);
+            }
+            continue;
+        }
+        files.push((modified, len, path));
+    }
+    files.sort_by_key(|(modified, _, _)| std::cmp::Reverse(*modified));
+    let mut total = 0;
+    for (_, len, path) in files {
+        total += len;
+        if total > ART_CACHE_MAX_BYTES {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+/// Moves a finished temp file into place, so readers never see a partial
+/// image.
+fn store_art(temp: &Path, target: &Path) -> bool {
+    if fs::rename(temp, target).is_err() {
+        let _ = fs::remove_file(temp);
+        return false;
+    }
+    prune_art_cache();
+    true
+}
+
+/// Downloads remote artwork on a background thread and re-emits the player's
+/// state once it is in the cache.
+fn download_art(url: &str, target: PathBuf, player_id: &str) {
+    let is_new = art_downloads()
+        .lock()
+        .map(|mut d| d.insert(target.clone()))
+        .unwrap_or(false);
+    if !is_new {
+        return;
+    }
+    let url = url.to_string();
+    let player_id = player_id.to_string();
+    std::thread::spawn(move || {
+        let temp = art_temp_path(&target);
+        let temp_str = temp.to_string_lossy().to_string();
+        let downloaded = run_command(
+            "curl",
+            &["-fsSL", "--max-time", "10", "-o", &temp_str, &url],
+        )
+        .is_ok();
+        let stored = if downloaded {
+            store_art(&temp, &target)
+        } else {
+            let _ = fs::remove_file(&temp);
+            false
+        };
+        if let Ok(mut downloads) = art_downloads().lock() {
+            downloads.remove(&target);
+        }
+
+        if let (true, Some(app)) = (stored, MEDIA_APP.get()) {
+            if let Ok(player) = find_player(Some(&player_id)) {
+                let meta = player.get_metadata().ok();
+                let state = build_state(&player, meta.as_ref());
+                emit_media_changed(app, &player_id, Some(state));
+            }
+        }
+    });
+}
+
+/// Returns the cached copy of the artwork behind `url`. Players often point at
+/// temporary files (browsers) or remote URLs (Spotify), neither of which the
+/// UI can load directly. Local files are copied right away; remote artwork is
+/// fetched in the background and `None` is returned until it arrives.
+fn cache_art(url: &str, player_id: &str) -> Option<String> {
+    let target = art_target(url)?;
+    if target.is_file() {
+        // Mark as recently used for pruning.
+        let _ = fs::File::options()
+            .write(true)
+            .open(&target)
+            .and_then(|f| f.set_modified(SystemTime::now()));
+        return Some(target.to_string_lossy().to_string());
+    }
+    fs::create_dir_all(art_cache_dir()).ok()?;
+
+    if let Some(path) = url.strip_prefix("file://") {
+        let temp = art_temp_path(&target);
+        if fs::copy(percent_decode(path), &temp).is_err() {
+            let _ = fs::remove_file(&temp);
+            return None;
+        }
+        return store_art(&temp, &target).then(|| target.to_string_lossy().to_string());
+    }
+    if url.starts_with("http://") || url.starts_with("https://") {
+        download_art(url, target, player_id);
+    }
+    None
+}
+
+fn build_state(player: &Player, meta: Option<&Metadata>) -> MediaState {
+    let art_url = meta.and_then(|m| m.art_url()).map(String::from);
+    MediaState {
+        player_id: player.bus_name().to_string(),
+        identity: player.identity().to_string(),
+        status: player
+            .get_playback_status()
+            .map(status_name)
+            .unwrap_or("Stopped")
+            .to_string(),
+        title: meta.and_then(|m| m.title()).map(String::from),
+        artist: meta
+            .and_then(|m| m.artists())
+            .and_then(|a| a.first().map(|s| s.to_string())),
+        album: meta.and_then(|m| m.album_name()).map(String::from),
+        art_path: art_url
+            .as_deref()
+            .and_then(|url| cache_art(url, player.bus_name())),
+        art_url,
+        length_ms: meta.and_then(|m| m.length()).map(|d| d.as_millis() as u64),
+        position_ms: player.get_position().ok().map(|d| d.as_millis() as u64),
+        rate: player.get_playback_rate().unwrap_or(1.0),
+        shuffle: player.get_shuffle().ok(),
+        loop_status: player
+            .get_loop_status()
+            .ok()
+            .map(|s| loop_status_name(s).to_string()),
+        can_seek: player.can_seek().unwrap_or(false),
+    }
+}
+
+#[tauri::command]
+pub fn get_media_state(player: Option<String>) -> Option<MediaState> {
+    let player = find_player(player.as_deref()).ok()?;
+    let meta = player.get_metadata().ok();
+    Some(build_state(&player, meta.as_ref()))
+}
+
+/// Seeks relative to the current position (negative offsets seek backwards).
+#[tauri::command]
+pub fn media_seek(offset_ms: i64, player: Option<String>) -> Result<(), String> {
+    let p = find_player(player.as_deref())?;
+    p.seek(offset_ms.saturating_mul(1000))
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn media_set_position(position_ms: u64, player: Option<String>) -> Result<(), String> {
+    let p = find_player(player.as_deref())?;
+    let meta = p.get_metadata().map_err(|e| e.to_string())?;
+    let track_id = meta
+        .track_id()
+        .ok_or("The player does not report a track id")?;
+    p.set_position(track_id, &Duration::from_millis(position_ms))
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn media_set_shuffle(shuffle: bool, player: Option<String>) -> Result<(), String> {
+    let p = find_player(player.as_deref())?;
+    p.set_shuffle(shuffle).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn media_set_loop(status: &str, player: Option<String>) -> Result<(), String> {
+    let status = match status {
+        "None" | "none" => LoopStatus::None,
+        "Track" | "track" => LoopStatus::Track,
+        "Playlist" | "playlist" => LoopStatus::Playlist,
+        _ => return Err(format!("Unknown loop status: {status}")),
+    };
+    let p = find_player(player.as_deref())?;
+    p.set_loop_status(status).map_err(|e| e.to_string())
+}
+
+// ===== Now-Playing Listener =====
+//
+// One thread per MPRIS player blocks on the player's PropertiesChanged
+// signals (via `Player::events`) and emits `media://changed` as soon as the
+// track or playback state changes. A supervisor thread picks up players that
+// appear later.
+
+const PLAYER_SCAN_INTERVAL: Duration = Duration::from_secs(3);
+
+#[derive(Serialize, Clone)]
+pub struct MediaChanged {
+    pub player_id: String,
+    /// `None` once the player has shut down
+    pub state: Option<MediaState>,
+}
+
+static LISTENED_PLAYERS: OnceLock<Mutex<HashSet<String>>> = OnceLock::new();
+
+fn listened_players() -> &'static Mutex<HashSet<String>> {
+    LISTENED_PLAYERS.get_or_init(|| Mutex::new(HashSet::new()))
+}
+
+// The part of each player's state the audio topic shows. Seeks, volume and
+// position updates leave it alone and do not need a new audio sample.
+#[derive(PartialEq, Clone)]
+struct NowPlaying {
+    status: String,
+    title: Option<String>,
+    artist: Option<String>,
+}
+
+static NOW_PLAYING: OnceLock<Mutex<HashMap<String, NowPlaying>>> = OnceLock::new();
+
+/// Records `state` for `player_id` and reports whether the now-playing
+/// summary changed.
+fn now_playing_changed(player_id: &str, state: Option<&MediaState>) -> bool {
+    let summary = state.map(|s| NowPlaying {
+        status: s.status.clone(),
+        title: s.title.clone(),
+        artist: s.artist.clone(),
+    });
+    let Ok(mut now_playing) = NOW_PLAYING
+        .get_or_init(|| Mutex::new(HashMap::new()))
+        .lock()
+    else {
+        return true;
+    };
+    let previous = match &summary {
+        Some(summary) => now_playing.insert(player_id.to_string(), summary.clone()),
+        None => now_playing.remove(player_id),
+    };
+    previous != summary
+}
+
+fn emit_media_changed(app: &AppHandle, player_id: &str, state: Option<MediaState>) {
+    let changed = now_playing_changed(player_id, state.as_ref());
+    let playing = state
+        .as_ref()
+        .filter(|s| s.status == "Playing")
+        .map(|s| s.identity.clone());
+    let _ = app.emit(
+        "media://changed",
+        MediaChanged {
+            player_id: player_id.to_string(),
+            state,
+        },
+    );
+    if !changed {
+        return;
+    }
+    crate::inhibit::media_changed(app, player_id, playing.as_deref());
+    // Keep the island's now-playing text in step without waiting for the
+    // next audio sample.
+    if crate::sampler::has_subscribers("audio") {
+        let audio = serde_json::to_value(crate::get_audio_info()).unwrap_or_default();
+        crate::sampler::publish(app, "audio", audio);
+    }
+}
+
+fn listen_player(app: AppHandle, bus_name: String) {
+    // Players hold a non-Send D-Bus connection, so look the player up again
+    // on the listener thread.
+    let player = find_players()
+        .into_iter()
+        .find(|p| p.bus_name() == bus_name);
+
+    let mut gone = true;
+    if let Some(player) = player {
+        // Pick up a player that was already playing when it appeared.
+        let playing = player
+            .get_playback_status()
+            .is_ok_and(|status| status == PlaybackStatus::Playing);
+        crate::inhibit::media_changed(&app, &bus_name, playing.then(|| player.identity()));
+        match player.events() {
+            Ok(events) => {
+                for event in events {
+                    let meta = match event {
+                        Ok(Event::PlayerShutDown) => break,
+                        // A broken event stream does not mean the player
+                        // quit; the supervisor attaches a new listener.
+                        Err(_) => {
+                            gone = !player.is_running();
+                            break;
+                        }
+                        Ok(Event::TrackChanged(meta)) => Some(meta),
+                        Ok(_) => player.get_metadata().ok(),
+                    };
+                    let state = build_state(&player, meta.as_ref());
+                    emit_media_changed(&app, &bus_name, Some(state));
+                }
+            }
+            Err(_) => gone = !player.is_running(),
+        }
+    }
+
+    if let Ok(mut listened) = listened_players().lock() {
+        listened.remove(&bus_name);
+    }
+    if gone {
+        emit_media_changed(&app, &bus_name, None);
+    }
+}
+
+/// Starts the supervisor that attaches a listener to every MPRIS player.
+pub fn start_listener(app: &AppHandle) {
+    let _ = MEDIA_APP.set(app.clone());
+    let app = app.clone();
+    std::thread::spawn(move || {
+        // One D-Bus connection for the lifetime of the supervisor; it is
+        // only opened again after a failed lookup.
+        let mut finder = None;
+        loop {
+            if finder.is_none() {
+                finder = PlayerFinder::new().ok();
+            }
+            let players = match finder.as_ref().map(|f| f.find_all()) {
+                Some(Ok(players)) => players,
+                Some(Err(_)) => {
+                    finder = None;
+                    Vec::new()
+                }
+                None => Vec::new(),
+            };
+            for player in players {
+                let bus_name = player.bus_name().to_string();
+                let is_new = listened_players()
+                    .lock()
+                    .map(|mut l| l.insert(bus_name.clone()))
+                    .unwrap_or(false);
+                if is_new {
+                    let app = app.clone();
+                    std::thread::spawn(move || listen_player(app, bus_name));
+                }
+            }
+            std::thread::sleep(PLAYER_SCAN_INTERVAL);
+        }
+    });
+}
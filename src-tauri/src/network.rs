@@ -0,0 +1,1419 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+use zbus::blocking::Connection;
+use zbus::zvariant::{ObjectPath, OwnedObjectPath, OwnedValue, Value};
+
+use crate::nm::{
+    self, AccessPointProxyBlocking, ActiveConnectionProxyBlocking, ConnectionSettings,
+    DeviceProxyBlocking, SettingsConnectionProxyBlocking, WirelessProxyBlocking,
+};
+
+// ===== Network (NetworkManager) =====
+
+#[derive(Serialize, Clone, PartialEq)]
+pub struct NetworkInfo {
+    /// True when a wired, Wi-Fi or other real interface reaches beyond the
+    /// machine (NetworkManager state CONNECTED_SITE or CONNECTED_GLOBAL)
+    pub is_connected: bool,
+    /// Internet reachability: "unknown", "none", "portal", "limited" or "full"
+    pub connectivity: String,
+    pub ssid: Option<String>,
+    pub signal_strength: Option<i32>,
+    /// First IPv4 address of the primary interface
+    pub ip_address: Option<String>,
+    /// Interface carrying the default route, e.g. "enp3s0"
+    pub primary_interface: Option<String>,
+    /// Device type of the primary interface, e.g. "ethernet" or "wifi"
+    pub primary_type: Option<String>,
+    pub interfaces: Vec<NetworkDevice>,
+    pub vpns: Vec<VpnTunnel>,
+}
+
+#[derive(Serialize, Clone, PartialEq)]
+pub struct WifiNetwork {
+    pub ssid: String,
+    pub bssid: String,
+    /// 0 - 100
+    pub signal_strength: u8,
+    /// MHz
+    pub frequency: u32,
+    /// e.g. "WPA2", "WPA2 WPA3"; empty for open networks
+    pub security: String,
+    pub is_active: bool,
+}
+
+#[derive(Serialize, Clone, PartialEq)]
+pub struct NetworkDevice {
+    pub interface: String,
+    pub device_type: String,
+    pub state: String,
+    pub hw_address: Option<String>,
+    /// Name of the connection profile active on this device
+    pub connection: Option<String>,
+    pub ipv4_addresses: Vec<String>,
+    pub ipv6_addresses: Vec<String>,
+    pub gateway: Option<String>,
+    pub dns: Vec<String>,
+}
+
+/// An active VPN or WireGuard tunnel.
+#[derive(Serialize, Clone, PartialEq)]
+pub struct VpnTunnel {
+    pub name: String,
+    /// "vpn" for NetworkManager VPN plugins, "wireguard" for WireGuard
+    pub vpn_type: String,
+    pub interface: Option<String>,
+    pub state: String,
+}
+
+#[derive(Serialize, Clone, PartialEq)]
+pub struct ActiveNetworkConnection {
+    pub id: String,
+    pub uuid: String,
+    pub connection_type: String,
+    pub interfaces: Vec<String>,
+    pub is_default: bool,
+    pub is_vpn: bool,
+}
+
+pub(crate) fn device_paths(conn: &Connection) -> Result<Vec<OwnedObjectPath>, String> {
+    nm::manager(conn)?.get_devices().map_err(|e| e.to_string())
+}
+
+pub(crate) fn device_interface(conn: &Connection, path: &OwnedObjectPath) -> Option<String> {
+    DeviceProxyBlocking::new(conn, path).ok()?.interface().ok()
+}
+
+/// The Wi-Fi device to use: an activated one if any, otherwise the first.
+pub(crate) fn wifi_device(conn: &Connection) -> Result<OwnedObjectPath, String> {
+    let mut first = None;
+    for path in device_paths(conn)? {
+        let Ok(device) = DeviceProxyBlocking::new(conn, &path) else {
+            continue;
+        };
+        if device.device_type().ok() != Some(nm::NM_DEVICE_TYPE_WIFI) {
+            continue;
+        }
+        if device.state().ok() == Some(nm::NM_DEVICE_STATE_ACTIVATED) {
+            return Ok(path.clone());
+        }
+        if first.is_none() {
+            first = Some(path.clone());
+        }
+    }
+    first.ok_or_else(|| "No Wi-Fi device found".to_string())
+}
+
+fn access_point(conn: &Connection, path: &OwnedObjectPath, active: bool) -> Option<WifiNetwork> {
+    let ap = AccessPointProxyBlocking::new(conn, path).ok()?;
+    let ssid = String::from_utf8_lossy(&ap.ssid().ok()?).to_string();
+    Some(WifiNetwork {
+        ssid,
+        bssid: ap.hw_address().unwrap_or_default(),
+        signal_strength: ap.strength().unwrap_or(0),
+        frequency: ap.frequency().unwrap_or(0),
+        security: nm::security_from_flags(
+            ap.flags().unwrap_or(0),
+            ap.wpa_flags().unwrap_or(0),
+            ap.rsn_flags().unwrap_or(0),
+        ),
+        is_active: active,
+    })
+}
+
+/// Access points visible to a Wi-Fi device, with their D-Bus paths.
+pub(crate) fn access_points(
+    conn: &Connection,
+    device: &OwnedObjectPath,
+) -> Result<Vec<(OwnedObjectPath, WifiNetwork)>, String> {
+    let wireless = WirelessProxyBlocking::new(conn, device).map_err(|e| e.to_string())?;
+    let active = wireless.active_access_point().ok();
+    let paths = wireless
+        .get_all_access_points()
+        .map_err(|e| e.to_string())?;
+    Ok(paths
+        .into_iter()
+        .filter_map(|path| {
+            let network = access_point(conn, &path, active.as_ref() == Some(&path))?;
+            Some((path, network))
+        })
+        .collect())
+}
+
+fn active_connection(conn: &Connection, path: &OwnedObjectPath) -> Option<ActiveNetworkConnection> {
+    let active = ActiveConnectionProxyBlocking::new(conn, path).ok()?;
+    let interfaces = active
+        .devices()
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|d| device_interface(conn, d))
+        .collect();
+    Some(ActiveNetworkConnection {
+        id: active.id().unwrap_or_default(),
+        uuid: active.uuid().unwrap_or_default(),
+        connection_type: active.connection_type().unwrap_or_default(),
+        interfaces,
+        is_default: active.default().unwrap_or(false),
+        is_vpn: active.vpn().unwrap_or(false),
+    })
+}
+
+fn active_wifi_network(conn: &Connection) -> Option<(WifiNetwork, OwnedObjectPath)> {
+    let device = wifi_device(conn).ok()?;
+    let wireless = WirelessProxyBlocking::new(conn, &device).ok()?;
+    let ap = wireless.active_access_point().ok()?;
+    if nm::is_null_path(&ap) {
+        return None;
+    }
+    Some((access_point(conn, &ap, true)?, device.clone()))
+}
+
+impl NetworkInfo {
+    fn unknown() -> Self {
+        NetworkInfo {
+            is_connected: false,
+            connectivity: "unknown".to_string(),
+            ssid: None,
+            signal_strength: None,
+            ip_address: None,
+            primary_interface: None,
+            primary_type: None,
+            interfaces: Vec::new(),
+            vpns: Vec::new(),
+        }
+    }
+}
+
+#[tauri::command]
+pub fn get_network_info() -> NetworkInfo {
+    match nm::connection() {
+        Ok(conn) => network_info(&conn),
+        Err(_) => NetworkInfo::unknown(),
+    }
+}
+
+fn network_info(conn: &Connection) -> NetworkInfo {
+    let mut info = NetworkInfo::unknown();
+    let Ok(manager) = nm::manager(conn) else {
+        return info;
+    };
+
+    info.interfaces = device_paths(conn)
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|path| network_device(conn, path))
+        .collect();
+
+    let active_paths = manager.active_connections().unwrap_or_default();
+    info.vpns = vpn_tunnels(conn, &active_paths);
+    info.is_connected = manager
+        .state()
+        .is_ok_and(|state| state >= nm::NM_STATE_CONNECTED_SITE);
+    info.connectivity = nm::connectivity_name(manager.connectivity().unwrap_or(0)).to_string();
+
+    // The primary connection is the one holding the default route.
+    let primary_device = manager
+        .primary_connection()
+        .ok()
+        .filter(|p| !nm::is_null_path(p))
+        .and_then(|p| {
+            ActiveConnectionProxyBlocking::new(conn, &p)
+                .ok()?
+                .devices()
+                .ok()
+        })
+        .and_then(|devices| devices.into_iter().next())
+        .and_then(|d| device_interface(conn, &d));
+    let primary = primary_device
+        .as_ref()
+        .and_then(|name| info.interfaces.iter().find(|i| &i.interface == name));
+    info.primary_type = primary.map(|i| i.device_type.clone());
+    info.ip_address = primary
+        .or_else(|| {
+            info.interfaces
+                .iter()
+                .find(|i| !i.ipv4_addresses.is_empty())
+        })
+        .and_then(|i| i.ipv4_addresses.first().cloned());
+    info.primary_interface = primary_device;
+
+    if let Some((network, _)) = active_wifi_network(conn) {
+        info.ssid = Some(network.ssid);
+        info.signal_strength = Some(network.signal_strength as i32);
+    }
+    info
+}
+
+fn network_device(conn: &Connection, path: &OwnedObjectPath) -> Option<NetworkDevice> {
+    let device = DeviceProxyBlocking::new(conn, path).ok()?;
+    let device_type = device.device_type().unwrap_or(0);
+    if device_type == nm::NM_DEVICE_TYPE_LOOPBACK {
+        return None;
+    }
+    let connection = device
+        .active_connection()
+        .ok()
+        .filter(|p| !nm::is_null_path(p))
+        .and_then(|p| ActiveConnectionProxyBlocking::new(conn, &p).ok()?.id().ok());
+    let ip4 = device
+        .ip4_config()
+        .map(|p| nm::ip4_config(conn, &p))
+        .unwrap_or_default();
+    let ip6 = device
+        .ip6_config()
+        .map(|p| nm::ip6_config(conn, &p))
+        .unwrap_or_default();
+
+    let mut dns = ip4.dns;
+    dns.extend(ip6.dns);
+    Some(NetworkDevice {
+        interface: device.interface().ok()?,
+        device_type: nm::device_type_name(device_type).to_string(),
+        state: nm::device_state_name(device.state().unwrap_or(0)).to_string(),
+        hw_address: device.hw_address().ok().filter(|a| !a.is_empty()),
+        connection,
+        ipv4_addresses: ip4.addresses,
+        ipv6_addresses: ip6.addresses,
+        gateway: ip4.gateway.or(ip6.gateway),
+        dns,
+    })
+}
+
+fn vpn_tunnels(conn: &Connection, active_paths: &[OwnedObjectPath]) -> Vec<VpnTunnel> {
+    active_paths
+        .iter()
+        .filter_map(|path| {
+            let active = ActiveConnectionProxyBlocking::new(conn, path).ok()?;
+            let connection_type = active.connection_type().unwrap_or_default();
+            let vpn_type = if connection_type == "wireguard" {
+                "wireguard"
+            } else if active.vpn().unwrap_or(false) {
+                "vpn"
+            } else {
+                return None;
+            };
+            Some(VpnTunnel {
+                name: active.id().unwrap_or_default(),
+                vpn_type: vpn_type.to_string(),
+                interface: active
+                    .devices()
+                    .unwrap_or_default()
+                    .first()
+                    .and_then(|d| device_interface(conn, d)),
+                state: nm::active_connection_state_name(active.state().unwrap_or(0)).to_string(),
+            })
+        })
+        .collect()
+}
+
+#[tauri::command]
+pub fn list_network_devices() -> Result<Vec<NetworkDevice>, String> {
+    let conn = nm::connection()?;
+    Ok(device_paths(&conn)?
+        .iter()
+        .filter_map(|path| network_device(&conn, path))
+        .collect())
+}
+
+#[tauri::command]
+pub fn list_active_connections() -> Result<Vec<ActiveNetworkConnection>, String> {
+    let conn = nm::connection()?;
+    let paths = nm::manager(&conn)?
+        .active_connections()
+        .map_err(|e| e.to_string())?;
+    Ok(paths
+        .iter()
+        .filter_map(|p| active_connection(&conn, p))
+        .collect())
+}
+
+#[tauri::command]
+pub fn get_wifi_networks() -> Result<Vec<WifiNetwork>, String> {
+    let conn = nm::connection()?;
+    let device = wifi_device(&conn)?;
+    wifi_networks(&conn, &device)
+}
+
+fn wifi_networks(conn: &Connection, device: &OwnedObjectPath) -> Result<Vec<WifiNetwork>, String> {
+    let mut networks: Vec<WifiNetwork> = access_points(conn, device)?
+        .into_iter()
+        .map(|(_, n)| n)
+        .filter(|n| !n.ssid.is_empty())
+        .collect();
+
+    // One entry per SSID: keep the active or strongest access point.
+    networks.sort_by(|a, b| {
+        a.ssid
+            .cmp(&b.ssid)
+            .then(b.is_active.cmp(&a.is_active))
+            .then(b.signal_strength.cmp(&a.signal_strength))
+    });
+    networks.dedup_by(|a, b| a.ssid == b.ssid);
+    networks.sort_by(|a, b| {
+        b.is_active
+            .cmp(&a.is_active)
+            .then(b.signal_strength.cmp(&a.signal_strength))
+    });
+    Ok(networks)
+}
+
+const ENTERPRISE_UNSUPPORTED: &str =
+    "Enterprise (802.1X) networks must be set up in the system network settings";
+
+/// "sae" for WPA3-only networks; other personal networks take a WPA-PSK.
+/// Enterprise networks need an EAP method and identity, which a password
+/// alone cannot provide.
+fn key_mgmt_for(network: Option<&WifiNetwork>) -> Result<&'static str, String> {
+    let security = network.map(|n| n.security.as_str()).unwrap_or_default();
+    let has = |name: &str| security.split(' ').any(|s| s == name);
+    if has("802.1X") {
+        return Err(ENTERPRISE_UNSUPPORTED.to_string());
+    }
+    Ok(if has("WPA3") && !has("WPA2") {
+        "sae"
+    } else {
+        "wpa-psk"
+    })
+}
+
+fn wifi_settings<'a>(
+    ssid: &str,
+    password: &'a str,
+    key_mgmt: &'a str,
+    hidden: bool,
+) -> HashMap<&'static str, HashMap<&'static str, Value<'a>>> {
+    let mut wireless: HashMap<&str, Value> = HashMap::new();
+    wireless.insert("ssid", Value::from(ssid.as_bytes().to_vec()));
+    if hidden {
+        wireless.insert("hidden", Value::from(true));
+    }
+    let mut settings = HashMap::new();
+    settings.insert("802-11-wireless", wireless);
+
+    if !password.is_empty() {
+        let mut security: HashMap<&str, Value> = HashMap::new();
+        security.insert("key-mgmt", Value::from(key_mgmt));
+        security.insert("psk", Value::from(password));
+        settings.insert("802-11-wireless-security", security);
+    }
+    settings
+}
+
+/// Activates the saved profile for `ssid` if there is one, storing `password`
+/// on it when given and marking it hidden when `hidden`; otherwise creates a
+/// new profile.
+fn connect_wifi_on(
+    conn: &Connection,
+    device: &OwnedObjectPath,
+    ssid: &str,
+    password: &str,
+    hidden: bool,
+) -> Result<(), String> {
+    let best = access_points(conn, device)?
+        .into_iter()
+        .filter(|(_, n)| n.ssid == ssid)
+        .max_by_key(|(_, n)| n.signal_strength);
+    let network = best.as_ref().map(|(_, n)| n.clone());
+    let specific = best
+        .map(|(path, _)| ObjectPath::from(path))
+        .unwrap_or_else(|| ObjectPath::from_static_str_unchecked("/"));
+    let manager = nm::manager(conn)?;
+
+    if let Some((profile, saved)) = saved_wifi_profile(conn, ssid)? {
+        // A non-broadcast SSID is only found when NetworkManager probes for it.
+        let mark_hidden =
+            hidden && setting::<bool>(&saved, "802-11-wireless", "hidden") != Some(true);
+        if !password.is_empty() || mark_hidden {
+            update_profile(conn, &profile, |settings| {
+                if mark_hidden {
+                    settings
+                        .entry("802-11-wireless".to_string())
+                        .or_default()
+                        .insert("hidden".to_string(), nm::owned(true)?);
+                }
+                if password.is_empty() {
+                    return Ok(());
+                }
+                let security = settings
+                    .entry("802-11-wireless-security".to_string())
+                    .or_default();
+                match security
+                    .get("key-mgmt")
+                    .and_then(|v| <&str>::try_from(v).ok())
+                {
+                    Some("wpa-eap" | "wpa-eap-suite-b-192" | "ieee8021x") => {
+                        return Err(ENTERPRISE_UNSUPPORTED.to_string())
+                    }
+                    Some(_) => {}
+                    None => {
+                        let key_mgmt = key_mgmt_for(network.as_ref())?;
+                        security.insert("key-mgmt".to_string(), nm::owned(key_mgmt)?);
+                    }
+                }
+                security.insert("psk".to_string(), nm::owned(password)?);
+                Ok(())
+            })?;
+        }
+        manager
+            .activate_connection(&profile, device, &specific)
+            .map_err(|e| e.to_string())?;
+        return Ok(());
+    }
+
+    let key_mgmt = key_mgmt_for(network.as_ref())?;
+    manager
+        .add_and_activate_connection(
+            wifi_settings(ssid, password, key_mgmt, hidden),
+            device,
+            &specific,
+        )
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn connect_wifi(ssid: &str, password: &str) -> Result<(), String> {
+    let conn = nm::connection()?;
+    let device = wifi_device(&conn)?;
+    connect_wifi_on(&conn, &device, ssid, password, false)
+}
+
+/// Connects to a network that does not broadcast its SSID.
+#[tauri::command]
+pub fn connect_hidden_wifi(
+    ssid: &str,
+    password: &str,
+    interface: Option<String>,
+) -> Result<(), String> {
+    if ssid.is_empty() {
+        return Err("SSID must not be empty".to_string());
+    }
+    let conn = nm::connection()?;
+    let device = resolve_wifi_device(&conn, interface.as_deref())?;
+    connect_wifi_on(&conn, &device, ssid, password, true)
+}
+
+/// Looks up a Wi-Fi device by interface name, or picks the active one.
+pub(crate) fn resolve_wifi_device(
+    conn: &Connection,
+    interface: Option<&str>,
+) -> Result<OwnedObjectPath, String> {
+    let Some(interface) = interface else {
+        return wifi_device(conn);
+    };
+    let path = device_paths(conn)?
+        .into_iter()
+        .find(|p| device_interface(conn, p).as_deref() == Some(interface))
+        .ok_or_else(|| format!("Network device not found: {interface}"))?;
+    let device_type = DeviceProxyBlocking::new(conn, &path)
+        .and_then(|d| d.device_type())
+        .map_err(|e| e.to_string())?;
+    if device_type != nm::NM_DEVICE_TYPE_WIFI {
+        return Err(format!("{interface} is not a Wi-Fi device"));
+    }
+    Ok(path)
+}
+
+#[tauri::command]
+pub fn list_wifi_devices() -> Result<Vec<NetworkDevice>, String> {
+    Ok(list_network_devices()?
+        .into_iter()
+        .filter(|d| d.device_type == "wifi")
+        .collect())
+}
+
+/// Disconnects the given (or the currently active) Wi-Fi device. NetworkManager
+/// will not auto-connect it again until the user connects explicitly.
+#[tauri::command]
+pub fn disconnect_wifi(interface: Option<String>) -> Result<(), String> {
+    let conn = nm::connection()?;
+    let device = resolve_wifi_device(&conn, interface.as_deref())?;
+    DeviceProxyBlocking::new(&conn, &device)
+        .and_then(|d| d.disconnect())
+        .map_err(|e| e.to_string())
+}
+
+// ===== Wi-Fi Radio & Scanning =====
+
+const SCAN_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Serialize, Clone)]
+pub struct WifiRadioState {
+    pub enabled: bool,
+    /// False when a hardware switch or rfkill blocks the radio
+    pub hardware_enabled: bool,
+}
+
+#[derive(Serialize, Clone)]
+pub struct WifiScanStatus {
+    pub scanning: bool,
+    /// Seconds since the scan was requested, while scanning
+    pub elapsed_secs: Option<u64>,
+}
+
+// The device being scanned, its LastScan value when the scan was requested,
+// and when. Only one scan is tracked at a time; while it is set, a poll
+// thread is waiting for it to finish.
+struct PendingScan {
+    device: OwnedObjectPath,
+    last_scan: i64,
+    requested_at: Instant,
+}
+
+static PENDING_SCAN: Mutex<Option<PendingScan>> = Mutex::new(None);
+
+#[tauri::command]
+pub fn get_wifi_radio() -> Result<WifiRadioState, String> {
+    let conn = nm::connection()?;
+    let manager = nm::manager(&conn)?;
+    Ok(WifiRadioState {
+        enabled: manager.wireless_enabled().map_err(|e| e.to_string())?,
+        hardware_enabled: manager.wireless_hardware_enabled().unwrap_or(true),
+    })
+}
+
+#[tauri::command]
+pub fn set_wifi_enabled(enabled: bool) -> Result<(), String> {
+    let conn = nm::connection()?;
+    nm::manager(&conn)?
+        .set_wireless_enabled(enabled)
+        .map_err(|e| e.to_string())
+}
+
+fn scan_done(scan: &PendingScan) -> bool {
+    if scan.requested_at.elapsed() > SCAN_TIMEOUT {
+        return true;
+    }
+    let last_scan = nm::connection().ok().and_then(|conn| {
+        WirelessProxyBlocking::new(&conn, &scan.device)
+            .ok()?
+            .last_scan()
+            .ok()
+    });
+    last_scan != Some(scan.last_scan)
+}
+
+fn scan_pending() -> Option<Instant> {
+    let pending = PENDING_SCAN.lock().ok()?;
+    pending
+        .as_ref()
+        .filter(|scan| !scan_done(scan))
+        .map(|scan| scan.requested_at)
+}
+
+/// Asks NetworkManager to rescan and emits `network://wifi-scan-done` with the
+/// fresh network list of that device once the scan has completed.
+#[tauri::command]
+pub fn request_wifi_scan(app: AppHandle, interface: Option<String>) -> Result<(), String> {
+    let conn = nm::connection()?;
+    let device = resolve_wifi_device(&conn, interface.as_deref())?;
+    let mut pending = PENDING_SCAN.lock().map_err(|e| e.to_string())?;
+    if pending
+        .as_ref()
+        .is_some_and(|scan| scan.device == device && !scan_done(scan))
+    {
+        // Already scanning; the running poll thread reports the result.
+        return Ok(());
+    }
+    let wireless = WirelessProxyBlocking::new(&conn, &device).map_err(|e| e.to_string())?;
+    let last_scan = wireless.last_scan().unwrap_or(-1);
+    wireless
+        .request_scan(HashMap::new())
+        .map_err(|e| e.to_string())?;
+    let poll = pending.is_none();
+    *pending = Some(PendingScan {
+        device: device.clone(),
+        last_scan,
+        requested_at: Instant::now(),
+    });
+    drop(pending);
+    if !poll {
+        return Ok(());
+    }
+
+    std::thread::spawn(move || loop {
+        std::thread::sleep(Duration::from_millis(500));
+        let Ok(mut pending) = PENDING_SCAN.lock() else {
+            return;
+        };
+        if pending.as_ref().is_some_and(|scan| !scan_done(scan)) {
+            continue;
+        }
+        let Some(scan) = pending.take() else {
+            return;
+        };
+        drop(pending);
+        let networks = nm::connection()
+            .and_then(|conn| wifi_networks(&conn, &scan.device))
+            .ok();
+        let _ = app.emit("network://wifi-scan-done", networks);
+        return;
+    });
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_wifi_scan_status() -> WifiScanStatus {
+    let requested_at = scan_pending();
+    WifiScanStatus {
+        scanning: requested_at.is_some(),
+        elapsed_secs: requested_at.map(|t| t.elapsed().as_secs()),
+    }
+}
+
+// ===== Saved Profiles =====
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Ipv4Settings {
+    /// "auto" (DHCP), "manual", "link-local", "shared" or "disabled"
+    pub method: String,
+    /// "address/prefix", e.g. "192.168.1.20/24"
+    pub addresses: Vec<String>,
+    pub gateway: Option<String>,
+    pub dns: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Ipv6Settings {
+    /// "auto" (SLAAC), "dhcp", "manual", "link-local", "shared", "ignore" or
+    /// "disabled"
+    pub method: String,
+    /// "address/prefix", e.g. "2001:db8::20/64"
+    pub addresses: Vec<String>,
+    pub gateway: Option<String>,
+    pub dns: Vec<String>,
+}
+
+#[derive(Serialize, Clone)]
+pub struct SavedConnection {
+    pub id: String,
+    pub uuid: String,
+    /// NetworkManager setting type, e.g. "802-11-wireless" or "802-3-ethernet"
+    pub connection_type: String,
+    pub ssid: Option<String>,
+    pub autoconnect: bool,
+    /// Higher values win when several profiles could autoconnect
+    pub autoconnect_priority: i32,
+    pub is_active: bool,
+    /// Unix time of the last successful activation
+    pub last_used: Option<u64>,
+    pub ipv4: Ipv4Settings,
+    pub ipv6: Ipv6Settings,
+}
+
+// Setting groups that may hold secrets. GetSettings never returns secrets, so
+// they are fetched separately and sent back on Update to keep them intact.
+const SECRET_SETTINGS: &[&str] = &["802-11-wireless-security", "802-1x", "vpn", "wireguard"];
+
+pub(crate) fn setting<T: TryFrom<OwnedValue>>(
+    settings: &ConnectionSettings,
+    group: &str,
+    key: &str,
+) -> Option<T> {
+    crate::bus::prop(settings.get(group)?, key)
+}
+
+/// "address/prefix" strings from the "address-data" of an IP group.
+fn address_data(settings: &ConnectionSettings, group: &str) -> Vec<String> {
+    let address_data: Vec<HashMap<String, OwnedValue>> =
+        setting(settings, group, "address-data").unwrap_or_default();
+    address_data
+        .iter()
+        .filter_map(|entry| {
+            let address = <&str>::try_from(entry.get("address")?).ok()?;
+            let prefix = u32::try_from(entry.get("prefix")?).ok()?;
+            Some(format!("{address}/{prefix}"))
+        })
+        .collect()
+}
+
+fn ipv4_settings(settings: &ConnectionSettings) -> Ipv4Settings {
+    // "dns-data" only exists on NetworkManager 1.42+; "dns" holds the same
+    // servers as u32s in network byte order.
+    let dns = setting::<Vec<String>>(settings, "ipv4", "dns-data").unwrap_or_else(|| {
+        setting::<Vec<u32>>(settings, "ipv4", "dns")
+            .unwrap_or_default()
+            .into_iter()
+            .map(|n| Ipv4Addr::from(n.to_ne_bytes()).to_string())
+            .collect()
+    });
+    Ipv4Settings {
+        method: setting(settings, "ipv4", "method").unwrap_or_else(|| "auto".to_string()),
+        addresses: address_data(settings, "ipv4"),
+        gateway: setting::<String>(settings, "ipv4", "gateway").filter(|g| !g.is_empty()),
+        dns,
+    }
+}
+
+fn ipv6_settings(settings: &ConnectionSettings) -> Ipv6Settings {
+    // The legacy "dns" form holds each server as 16 raw bytes.
+    let dns = setting::<Vec<String>>(settings, "ipv6", "dns-data").unwrap_or_else(|| {
+        setting::<Vec<Vec<u8>>>(settings, "ipv6", "dns")
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|raw| <[u8; 16]>::try_from(raw).ok())
+            .map(|octets| Ipv6Addr::from(octets).to_string())
+            .collect()
+    });
+    Ipv6Settings {
+        method: setting(settings, "ipv6", "method").unwrap_or_else(|| "auto".to_string()),
+        addresses: address_data(settings, "ipv6"),
+        gateway: setting::<String>(settings, "ipv6", "gateway").filter(|g| !g.is_empty()),
+        dns,
+    }
+}
+
+fn saved_connection(settings: &ConnectionSettings, active: &HashSet<String>) -> SavedConnection {
+    let uuid: String = setting(settings, "connection", "uuid").unwrap_or_default();
+    SavedConnection {
+        id: setting(settings, "connection", "id").unwrap_or_default(),
+        connection_type: setting(settings, "connection", "type").unwrap_or_default(),
+        ssid: setting::<Vec<u8>>(settings, "802-11-wireless", "ssid")
+            .map(|ssid| String::from_utf8_lossy(&ssid).to_string()),
+        // NetworkManager leaves out properties that are at their default.
+        autoconnect: setting(settings, "connection", "autoconnect").unwrap_or(true),
+        autoconnect_priority: setting(settings, "connection", "autoconnect-priority").unwrap_or(0),
+        is_active: active.contains(&uuid),
+        last_used: setting(settings, "connection", "timestamp").filter(|t: &u64| *t > 0),
+        ipv4: ipv4_settings(settings),
+        ipv6: ipv6_settings(settings),
+        uuid,
+    }
+}
+
+pub(crate) fn saved_profiles(
+    conn: &Connection,
+) -> Result<Vec<(OwnedObjectPath, ConnectionSettings)>, String> {
+    let paths = nm::settings(conn)?
+        .list_connections()
+        .map_err(|e| e.to_string())?;
+    Ok(paths
+        .into_iter()
+        .filter_map(|path| {
+            let settings = SettingsConnectionProxyBlocking::new(conn, &path)
+                .ok()?
+                .get_settings()
+                .ok()?;
+            Some((path, settings))
+        })
+        .collect())
+}
+
+/// The preferred saved profile for a Wi-Fi network, if any. Access point
+/// profiles (a hotspot with the same SSID) are never picked.
+fn saved_wifi_profile(
+    conn: &Connection,
+    ssid: &str,
+) -> Result<Option<(OwnedObjectPath, ConnectionSettings)>, String> {
+    Ok(saved_profiles(conn)?
+        .into_iter()
+        .filter(|(_, settings)| {
+            setting::<Vec<u8>>(settings, "802-11-wireless", "ssid").as_deref()
+                == Some(ssid.as_bytes())
+                && setting::<String>(settings, "802-11-wireless", "mode").as_deref() != Some("ap")
+        })
+        .max_by_key(|(_, settings)| {
+            (
+                setting::<i32>(settings, "connection", "autoconnect-priority").unwrap_or(0),
+                setting::<u64>(settings, "connection", "timestamp").unwrap_or(0),
+            )
+        }))
+}
+
+fn active_uuids(conn: &Connection) -> HashSet<String> {
+    nm::manager(conn)
+        .and_then(|m| m.active_connections().map_err(|e| e.to_string()))
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|p| {
+            ActiveConnectionProxyBlocking::new(conn, p)
+                .ok()?
+                .uuid()
+                .ok()
+        })
+        .collect()
+}
+
+pub(crate) fn profile_path(conn: &Connection, uuid: &str) -> Result<OwnedObjectPath, String> {
+    nm::settings(conn)?
+        .get_connection_by_uuid(uuid)
+        .map_err(|_| format!("No saved connection with UUID {uuid}"))
+}
+
+/// Reads a profile with its secrets, lets `edit` change it and writes it back.
+pub(crate) fn update_profile(
+    conn: &Connection,
+    path: &OwnedObjectPath,
+    edit: impl FnOnce(&mut ConnectionSettings) -> Result<(), String>,
+) -> Result<(), String> {
+    let profile = SettingsConnectionProxyBlocking::new(conn, path).map_err(|e| e.to_string())?;
+    let mut settings = profile.get_settings().map_err(|e| e.to_string())?;
+    for group in SECRET_SETTINGS {
+        if !settings.contains_key(*group) {
+            continue;
+        }
+        // Update replaces the whole profile, so writing it back without its
+        // secrets would erase them.
+        let secrets = profile
+            .get_secrets(group)
+            .map_err(|e| format!("Cannot read the {group} secrets of this profile: {e}"))?;
+        for (name, values) in secrets {
+            settings.entry(name).or_default().extend(values);
+        }
+    }
+    edit(&mut settings)?;
+    profile.update(settings).map_err(|e| e.to_string())
+}
+
+fn parse_ipv4(address: &str) -> Result<Ipv4Addr, String> {
+    address
+        .trim()
+        .parse()
+        .map_err(|_| format!("Invalid IPv4 address: {address}"))
+}
+
+fn parse_ipv6(address: &str) -> Result<Ipv6Addr, String> {
+    address
+        .trim()
+        .parse()
+        .map_err(|_| format!("Invalid IPv6 address: {address}"))
+}
+
+/// Splits "address/prefix"; `default` is used when the prefix is left out.
+fn split_cidr(cidr: &str, default: u32, max: u32) -> Result<(&str, u32), String> {
+    let (address, prefix) = match cidr.split_once('/') {
+        Some((address, prefix)) => (address, prefix.trim().parse::<u32>().ok()),
+        None => (cidr, Some(default)),
+    };
+    let prefix = prefix
+        .filter(|p| *p <= max)
+        .ok_or_else(|| format!("Invalid prefix length in {cidr}"))?;
+    Ok((address, prefix))
+}
+
+/// Parses "192.168.1.20/24"; the prefix defaults to 24.
+fn parse_ipv4_cidr(cidr: &str) -> Result<(Ipv4Addr, u32), String> {
+    let (address, prefix) = split_cidr(cidr, 24, 32)?;
+    Ok((parse_ipv4(address)?, prefix))
+}
+
+/// Parses "2001:db8::20/64"; the prefix defaults to 64.
+fn parse_ipv6_cidr(cidr: &str) -> Result<(Ipv6Addr, u32), String> {
+    let (address, prefix) = split_cidr(cidr, 64, 128)?;
+    Ok((parse_ipv6(address)?, prefix))
+}
+
+fn address_entry(address: String, prefix: u32) -> HashMap<&'static str, Value<'static>> {
+    let mut entry: HashMap<&str, Value> = HashMap::new();
+    entry.insert("address", Value::from(address));
+    entry.insert("prefix", Value::from(prefix));
+    entry
+}
+
+/// Validated addressing for the "ipv4" or "ipv6" group.
+struct IpGroup<'a> {
+    method: &'a str,
+    address_data: Vec<HashMap<&'static str, Value<'static>>>,
+    gateway: Option<&'a str>,
+    dns: Option<OwnedValue>,
+}
+
+fn write_ip_group(
+    settings: &mut ConnectionSettings,
+    name: &str,
+    ip: IpGroup,
+) -> Result<(), String> {
+    if ip.method == "manual" && ip.address_data.is_empty() {
+        return Err("A static configuration needs at least one address".to_string());
+    }
+    let group = settings.entry(name.to_string()).or_default();
+    // Drop the legacy and derived forms so they cannot contradict the new values.
+    for key in [
+        "addresses",
+        "address-data",
+        "gateway",
+        "dns",
+        "dns-data",
+        "ignore-auto-dns",
+    ] {
+        group.remove(key);
+    }
+    group.insert("method".to_string(), nm::owned(ip.method)?);
+    if !ip.address_data.is_empty() {
+        group.insert("address-data".to_string(), nm::owned(ip.address_data)?);
+    }
+    if let Some(gateway) = ip.gateway {
+        group.insert("gateway".to_string(), nm::owned(gateway.trim())?);
+    }
+    if let Some(dns) = ip.dns {
+        group.insert("dns".to_string(), dns);
+        // Custom DNS servers replace the ones handed out by DHCP.
+        if matches!(ip.method, "auto" | "dhcp") {
+            group.insert("ignore-auto-dns".to_string(), nm::owned(true)?);
+        }
+    }
+    Ok(())
+}
+
+fn apply_ipv4(settings: &mut ConnectionSettings, ipv4: &Ipv4Settings) -> Result<(), String> {
+    let mut address_data = Vec::new();
+    for cidr in &ipv4.addresses {
+        let (address, prefix) = parse_ipv4_cidr(cidr)?;
+        address_data.push(address_entry(address.to_string(), prefix));
+    }
+    let dns = ipv4
+        .dns
+        .iter()
+        .map(|d| parse_ipv4(d).map(|ip| u32::from_ne_bytes(ip.octets())))
+        .collect::<Result<Vec<u32>, String>>()?;
+    let gateway = ipv4.gateway.as_deref().filter(|g| !g.trim().is_empty());
+    if let Some(gateway) = gateway {
+        parse_ipv4(gateway)?;
+    }
+    let dns = if dns.is_empty() {
+        None
+    } else {
+        Some(nm::owned(dns)?)
+    };
+    write_ip_group(
+        settings,
+        "ipv4",
+        IpGroup {
+            method: &ipv4.method,
+            address_data,
+            gateway,
+            dns,
+        },
+    )
+}
+
+fn apply_ipv6(settings: &mut ConnectionSettings, ipv6: &Ipv6Settings) -> Result<(), String> {
+    let mut address_data = Vec::new();
+    for cidr in &ipv6.addresses {
+        let (address, prefix) = parse_ipv6_cidr(cidr)?;
+        address_data.push(address_entry(address.to_string(), prefix));
+    }
+    let dns = ipv6
+        .dns
+        .iter()
+        .map(|d| parse_ipv6(d).map(|ip| ip.octets().to_vec()))
+        .collect::<Result<Vec<Vec<u8>>, String>>()?;
+    let gateway = ipv6.gateway.as_deref().filter(|g| !g.trim().is_empty());
+    if let Some(gateway) = gateway {
+        parse_ipv6(gateway)?;
+    }
+    let dns = if dns.is_empty() {
+        None
+    } else {
+        Some(nm::owned(dns)?)
+    };
+    write_ip_group(
+        settings,
+        "ipv6",
+        IpGroup {
+            method: &ipv6.method,
+            address_data,
+            gateway,
+            dns,
+        },
+    )
+}
+
+/// Re-activates a profile that is in use so edited settings take effect.
+fn reactivate_if_active(
+    conn: &Connection,
+    uuid: &str,
+    path: &OwnedObjectPath,
+) -> Result<(), String> {
+    if active_uuids(conn).contains(uuid) {
+        let none = ObjectPath::from_static_str_unchecked("/");
+        nm::manager(conn)?
+            .activate_connection(path, &none, &none)
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn list_saved_connections() -> Result<Vec<SavedConnection>, String> {
+    let conn = nm::connection()?;
+    let active = active_uuids(&conn);
+    let mut profiles: Vec<SavedConnection> = saved_profiles(&conn)?
+        .iter()
+        .map(|(_, settings)| saved_connection(settings, &active))
+        .filter(|p| p.connection_type != "loopback")
+        .collect();
+    profiles.sort_by(|a, b| {
+        b.is_active
+            .cmp(&a.is_active)
+            .then(b.last_used.cmp(&a.last_used))
+    });
+    Ok(profiles)
+}
+
+#[tauri::command]
+pub fn forget_connection(uuid: &str) -> Result<(), String> {
+    let conn = nm::connection()?;
+    let path = profile_path(&conn, uuid)?;
+    SettingsConnectionProxyBlocking::new(&conn, &path)
+        .and_then(|p| p.delete())
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn set_connection_autoconnect(
+    uuid: &str,
+    autoconnect: bool,
+    priority: Option<i32>,
+) -> Result<(), String> {
+    let conn = nm::connection()?;
+    let path = profile_path(&conn, uuid)?;
+    update_profile(&conn, &path, |settings| {
+        let group = settings.entry("connection".to_string()).or_default();
+        group.insert("autoconnect".to_string(), nm::owned(autoconnect)?);
+        if let Some(priority) = priority {
+            // NetworkManager accepts -999 to 999.
+            group.insert(
+                "autoconnect-priority".to_string(),
+                nm::owned(priority.clamp(-999, 999))?,
+            );
+        }
+        Ok(())
+    })
+}
+
+/// Replaces the IPv4 addressing of a profile. An active profile is
+/// re-activated so the new settings take effect immediately.
+#[tauri::command]
+pub fn set_connection_ipv4(uuid: &str, ipv4: Ipv4Settings) -> Result<(), String> {
+    let conn = nm::connection()?;
+    let path = profile_path(&conn, uuid)?;
+    update_profile(&conn, &path, |settings| apply_ipv4(settings, &ipv4))?;
+    reactivate_if_active(&conn, uuid, &path)
+}
+
+/// Replaces the IPv6 addressing of a profile, like `set_connection_ipv4`.
+#[tauri::command]
+pub fn set_connection_ipv6(uuid: &str, ipv6: Ipv6Settings) -> Result<(), String> {
+    let conn = nm::connection()?;
+    let path = profile_path(&conn, uuid)?;
+    update_profile(&conn, &path, |settings| apply_ipv6(settings, &ipv6))?;
+    reactivate_if_active(&conn, uuid, &path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nm_mock::{settings, MockAccessPoint, MockActive, MockDevice, MockNm, MockProfile};
+
+    fn owned<'a>(value: impl Into<Value<'a>>) -> OwnedValue {
+        nm::owned(value).unwrap()
+    }
+
+    fn wifi(access_points: Vec<MockAccessPoint>) -> MockDevice {
+        MockDevice {
+            interface: "wlan0",
+            device_type: nm::NM_DEVICE_TYPE_WIFI,
+            state: 30,
+            access_points,
+        }
+    }
+
+    fn psk_profile(secrets: Option<ConnectionSettings>) -> MockProfile {
+        MockProfile {
+            settings: settings(vec![
+                ("connection", "id", owned("Home")),
+                ("connection", "uuid", owned("home-uuid")),
+                ("connection", "type", owned("802-11-wireless")),
+                ("802-11-wireless", "ssid", owned(b"Home".to_vec())),
+                ("802-11-wireless-security", "key-mgmt", owned("wpa-psk")),
+            ]),
+            secrets,
+        }
+    }
+
+    #[test]
+    fn loopback_alone_is_not_connected() {
+        let mock = MockNm {
+            // CONNECTED_LOCAL
+            state: 50,
+            devices: vec![MockDevice {
+                interface: "lo",
+                device_type: nm::NM_DEVICE_TYPE_LOOPBACK,
+                state: nm::NM_DEVICE_STATE_ACTIVATED,
+                ..Default::default()
+            }],
+            profiles: vec![MockProfile {
+                settings: settings(vec![
+                    ("connection", "id", owned("lo")),
+                    ("connection", "uuid", owned("lo-uuid")),
+                    ("connection", "type", owned("loopback")),
+                ]),
+                secrets: None,
+            }],
+            active: vec![MockActive {
+                profile: 0,
+                device: 0,
+            }],
+            ..Default::default()
+        }
+        .serve();
+
+        let info = network_info(&mock.conn);
+        assert!(!info.is_connected);
+        assert!(info.interfaces.is_empty());
+    }
+
+    #[test]
+    fn primary_connection_names_the_interface() {
+        let mock = MockNm {
+            // CONNECTED_GLOBAL
+            state: 70,
+            connectivity: 4,
+            devices: vec![MockDevice {
+                interface: "enp3s0",
+                device_type: 1,
+                state: nm::NM_DEVICE_STATE_ACTIVATED,
+                ..Default::default()
+            }],
+            profiles: vec![MockProfile {
+                settings: settings(vec![
+                    ("connection", "id", owned("Wired")),
+                    ("connection", "uuid", owned("wired-uuid")),
+                    ("connection", "type", owned("802-3-ethernet")),
+                ]),
+                secrets: None,
+            }],
+            active: vec![MockActive {
+                profile: 0,
+                device: 0,
+            }],
+            primary: Some(0),
+        }
+        .serve();
+
+        let info = network_info(&mock.conn);
+        assert!(info.is_connected);
+        assert_eq!(info.connectivity, "full");
+        assert_eq!(info.primary_interface.as_deref(), Some("enp3s0"));
+        assert_eq!(info.primary_type.as_deref(), Some("ethernet"));
+        assert_eq!(info.interfaces[0].connection.as_deref(), Some("Wired"));
+    }
+
+    #[test]
+    fn wpa3_only_networks_use_sae() {
+        let mock = MockNm {
+            devices: vec![wifi(vec![MockAccessPoint {
+                ssid: "Cafe",
+                strength: 70,
+                flags: 1,
+                rsn_flags: 0x400,
+                ..Default::default()
+            }])],
+            ..Default::default()
+        }
+        .serve();
+        let device = wifi_device(&mock.conn).unwrap();
+
+        connect_wifi_on(&mock.conn, &device, "Cafe", "secret123", false).unwrap();
+        let calls = mock.calls.lock().unwrap();
+        let security = &calls.added[0]["802-11-wireless-security"];
+        assert_eq!(<&str>::try_from(&security["key-mgmt"]).unwrap(), "sae");
+        assert_eq!(<&str>::try_from(&security["psk"]).unwrap(), "secret123");
+    }
+
+    #[test]
+    fn enterprise_networks_are_refused() {
+        let mock = MockNm {
+            devices: vec![wifi(vec![MockAccessPoint {
+                ssid: "Campus",
+                strength: 70,
+                flags: 1,
+                rsn_flags: 0x200,
+                ..Default::default()
+            }])],
+            ..Default::default()
+        }
+        .serve();
+        let device = wifi_device(&mock.conn).unwrap();
+
+        let result = connect_wifi_on(&mock.conn, &device, "Campus", "hunter2", false);
+        assert_eq!(result, Err(ENTERPRISE_UNSUPPORTED.to_string()));
+        assert!(mock.calls.lock().unwrap().added.is_empty());
+    }
+
+    #[test]
+    fn saved_profiles_are_reused() {
+        let secrets = settings(vec![(
+            "802-11-wireless-security",
+            "psk",
+            owned("old-password"),
+        )]);
+        let mock = MockNm {
+            devices: vec![wifi(vec![MockAccessPoint {
+                ssid: "Home",
+                strength: 50,
+                flags: 1,
+                rsn_flags: 0x100,
+                ..Default::default()
+            }])],
+            profiles: vec![psk_profile(Some(secrets))],
+            ..Default::default()
+        }
+        .serve();
+        let device = wifi_device(&mock.conn).unwrap();
+
+        connect_wifi_on(&mock.conn, &device, "Home", "", false).unwrap();
+        let calls = mock.calls.lock().unwrap();
+        assert!(calls.added.is_empty());
+        assert!(calls.updated.is_empty());
+        assert_eq!(calls.activated.len(), 1);
+        assert_eq!(calls.activated[0].1, device);
+    }
+
+    #[test]
+    fn hidden_networks_mark_saved_profiles_hidden() {
+        let secrets = settings(vec![(
+            "802-11-wireless-security",
+            "psk",
+            owned("old-password"),
+        )]);
+        let mock = MockNm {
+            devices: vec![wifi(Vec::new())],
+            profiles: vec![psk_profile(Some(secrets))],
+            ..Default::default()
+        }
+        .serve();
+        let device = wifi_device(&mock.conn).unwrap();
+
+        connect_wifi_on(&mock.conn, &device, "Home", "", true).unwrap();
+        let calls = mock.calls.lock().unwrap();
+        let (_, updated) = &calls.updated[0];
+        assert_eq!(
+            bool::try_from(&updated["802-11-wireless"]["hidden"]),
+            Ok(true)
+        );
+        assert_eq!(calls.activated.len(), 1);
+    }
+
+    #[test]
+    fn access_point_profiles_are_not_joined() {
+        let mut hotspot = psk_profile(None);
+        hotspot
+            .settings
+            .entry("802-11-wireless".to_string())
+            .or_default()
+            .insert("mode".to_string(), owned("ap"));
+        let mock = MockNm {
+            devices: vec![wifi(vec![MockAccessPoint {
+                ssid: "Home",
+                strength: 50,
+                flags: 1,
+                rsn_flags: 0x100,
+                ..Default::default()
+            }])],
+            profiles: vec![hotspot],
+            ..Default::default()
+        }
+        .serve();
+        let device = wifi_device(&mock.conn).unwrap();
+
+        connect_wifi_on(&mock.conn, &device, "Home", "password1", false).unwrap();
+        let calls = mock.calls.lock().unwrap();
+        assert!(calls.activated.is_empty());
+        assert_eq!(calls.added.len(), 1);
+    }
+
+    #[test]
+    fn updates_keep_secrets() {
+        let secrets = settings(vec![(
+            "802-11-wireless-security",
+            "psk",
+            owned("password1"),
+        )]);
+        let mock = MockNm {
+            profiles: vec![psk_profile(Some(secrets))],
+            ..Default::default()
+        }
+        .serve();
+        let path = profile_path(&mock.conn, "home-uuid").unwrap();
+
+        update_profile(&mock.conn, &path, |settings| {
+            let group = settings.entry("connection".to_string()).or_default();
+            group.insert("autoconnect".to_string(), nm::owned(false)?);
+            Ok(())
+        })
+        .unwrap();
+        let calls = mock.calls.lock().unwrap();
+        let (_, updated) = &calls.updated[0];
+        let psk = &updated["802-11-wireless-security"]["psk"];
+        assert_eq!(<&str>::try_from(psk).unwrap(), "password1");
+        assert_eq!(
+            bool::try_from(&updated["connection"]["autoconnect"]),
+            Ok(false)
+        );
+    }
+
+    #[test]
+    fn updates_stop_when_secrets_are_unreadable() {
+        let mock = MockNm {
+            profiles: vec![psk_profile(None)],
+            ..Default::default()
+        }
+        .serve();
+        let path = profile_path(&mock.conn, "home-uuid").unwrap();
+
+        let result = update_profile(&mock.conn, &path, |_| Ok(()));
+        assert!(result.is_err());
+        assert!(mock.calls.lock().unwrap().updated.is_empty());
+    }
+
+    #[test]
+    fn only_wifi_devices_are_resolved() {
+        let mock = MockNm {
+            devices: vec![
+                MockDevice {
+                    interface: "enp3s0",
+                    device_type: 1,
+                    state: nm::NM_DEVICE_STATE_ACTIVATED,
+                    ..Default::default()
+                },
+                wifi(Vec::new()),
+            ],
+            ..Default::default()
+        }
+        .serve();
+
+        assert!(resolve_wifi_device(&mock.conn, Some("enp3s0")).is_err());
+        let wlan = resolve_wifi_device(&mock.conn, Some("wlan0")).unwrap();
+        assert_eq!(resolve_wifi_device(&mock.conn, None), Ok(wlan));
+    }
+
+    #[test]
+    fn ipv6_settings_round_trip() {
+        let mut profile = ConnectionSettings::new();
+        let ipv6 = Ipv6Settings {
+            method: "manual".to_string(),
+            addresses: vec!["2001:db8::20/64".to_string(), "2001:db8::21".to_string()],
+            gateway: Some("2001:db8::1".to_string()),
+            dns: vec!["2001:4860:4860::8888".to_string()],
+        };
+        apply_ipv6(&mut profile, &ipv6).unwrap();
+
+        let read = ipv6_settings(&profile);
+        assert_eq!(read.method, "manual");
+        assert_eq!(read.addresses, ["2001:db8::20/64", "2001:db8::21/64"]);
+        assert_eq!(read.gateway.as_deref(), Some("2001:db8::1"));
+        assert_eq!(read.dns, ["2001:4860:4860::8888"]);
+
+        let invalid = Ipv6Settings {
+            addresses: vec!["2001:db8::20/129".to_string()],
+            ..ipv6
+        };
+        assert!(apply_ipv6(&mut profile, &invalid).is_err());
+    }
+}
@@ -0,0 +1,520 @@
+use chrono::{Datelike, Local, Timelike};
+use serde::Serialize;
+use std::f64::consts::PI;
+use std::fs;
+use std::path::PathBuf;
+use std::process::{Child, Command};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use crate::{load_settings, run_command, save_settings_internal, DesktopSettings};
+
+// ===== Night Light =====
+//
+// On GNOME the settings daemon does the color work and we only write its
+// org.gnome.settings-daemon.plugins.color keys, once night light has been
+// switched on here; the user's own values come back when it is switched off
+// again. Elsewhere gammastep (or
+// redshift) sets the gamma ramps, and a thread switches it on and off
+// according to the schedule. Sunset and sunrise are computed locally from
+// the configured coordinates, so no location service is involved.
+
+const COLOR_SCHEMA: &str = "org.gnome.settings-daemon.plugins.color";
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+const MIN_TEMPERATURE: u32 = 1700;
+const MAX_TEMPERATURE: u32 = 6500;
+const NO_BACKEND: &str = "Night light needs GNOME, gammastep or redshift";
+
+/// GNOME keys written while night light is on, in the order they are set.
+const GNOME_KEYS: &[&str] = &[
+    "night-light-schedule-automatic",
+    "night-light-schedule-from",
+    "night-light-schedule-to",
+    "night-light-temperature",
+    "night-light-enabled",
+];
+
+#[derive(Serialize, Clone)]
+pub struct NightLightStatus {
+    pub enabled: bool,
+    /// Whether the screen is tinted right now
+    pub active: bool,
+    /// Kelvin
+    pub temperature: u32,
+    /// "manual", "fixed" or "sunset"
+    pub schedule: String,
+    /// Hours of the day, e.g. 20.5 for 20:30
+    pub from: f64,
+    pub to: f64,
+    /// Today's sunset and sunrise in local hours, when coordinates are set
+    pub sunset: Option<f64>,
+    pub sunrise: Option<f64>,
+    /// "gnome", "gammastep", "redshift" or "none"
+    pub backend: String,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Backend {
+    Gnome,
+    Gamma(&'static str),
+    None,
+}
+
+static BACKEND: OnceLock<Backend> = OnceLock::new();
+
+fn backend() -> Backend {
+    *BACKEND.get_or_init(|| {
+        let gnome = std::env::var("XDG_CURRENT_DESKTOP")
+            .is_ok_and(|d| d.split(':').any(|d| d.eq_ignore_ascii_case("GNOME")));
+        if gnome && run_command("gsettings", &["get", COLOR_SCHEMA, "night-light-enabled"]).is_ok()
+        {
+            return Backend::Gnome;
+        }
+        ["gammastep", "redshift"]
+            .into_iter()
+            .find(|tool| Command::new(tool).arg("-V").output().is_ok())
+            .map(Backend::Gamma)
+            .unwrap_or(Backend::None)
+    })
+}
+
+fn backend_name(backend: Backend) -> &'static str {
+    match backend {
+        Backend::Gnome => "gnome",
+        Backend::Gamma(tool) => tool,
+        Backend::None => "none",
+    }
+}
+
+// ===== Schedule =====
+
+/// Sunset and sunrise in local hours for today, using NOAA's approximation.
+/// `from == to` means the sun does not rise (polar night); `None` means it
+/// does not set.
+fn sun_window(latitude: f64, longitude: f64) -> Option<(f64, f64)> {
+    let now = Local::now();
+    let offset_minutes = f64::from(now.offset().local_minus_utc()) / 60.0;
+    sun_window_on(latitude, longitude, now.ordinal0(), offset_minutes)
+}
+
+/// `sun_window` for day `ordinal0` of the year (0 is January 1st) in a time
+/// zone `offset_minutes` ahead of UTC.
+fn sun_window_on(
+    latitude: f64,
+    longitude: f64,
+    ordinal0: u32,
+    offset_minutes: f64,
+) -> Option<(f64, f64)> {
+    let gamma = 2.0 * PI / 365.0 * (ordinal0 as f64);
+    let eq_time = 229.18
+        * (0.000075 + 0.001868 * gamma.cos()
+            - 0.032077 * gamma.sin()
+            - 0.014615 * (2.0 * gamma).cos()
+            - 0.040849 * (2.0 * gamma).sin());
+    let declination = 0.006918 - 0.399912 * gamma.cos() + 0.070257 * gamma.sin()
+        - 0.006758 * (2.0 * gamma).cos()
+        + 0.000907 * (2.0 * gamma).sin()
+        - 0.002697 * (3.0 * gamma).cos()
+        + 0.00148 * (3.0 * gamma).sin();
+
+    let lat = latitude.to_radians();
+    let cos_hour_angle = 90.833_f64.to_radians().cos() / (lat.cos() * declination.cos())
+        - lat.tan() * declination.tan();
+    if cos_hour_angle > 1.0 {
+        return Some((0.0, 0.0));
+    }
+    if cos_hour_angle < -1.0 {
+        return None;
+    }
+    let hour_angle = cos_hour_angle.acos().to_degrees();
+
+    let to_local = |utc_minutes: f64| ((utc_minutes + offset_minutes) / 60.0).rem_euclid(24.0);
+    let sunrise = to_local(720.0 - 4.0 * (longitude + hour_angle) - eq_time);
+    let sunset = to_local(720.0 - 4.0 * (longitude - hour_angle) - eq_time);
+    Some((sunset, sunrise))
+}
+
+/// The part of the day night light should be on, as (from, to) hours;
+/// `from == to` covers the whole day. `None` when it should stay off.
+fn night_window(settings: &DesktopSettings) -> Option<(f64, f64)> {
+    if !settings.night_light_enabled {
+        return None;
+    }
+    match settings.night_light_schedule.as_str() {
+        "fixed" => Some((settings.night_light_from, settings.night_light_to)),
+        "sunset" => sun_window(
+            settings.night_light_latitude?,
+            settings.night_light_longitude?,
+        ),
+        _ => Some((0.0, 0.0)),
+    }
+}
+
+/// Same rule as gnome-settings-daemon: windows may wrap past midnight.
+fn in_window(hour: f64, (from, to): (f64, f64)) -> bool {
+    if from == to {
+        true
+    } else if from < to {
+        hour >= from && hour < to
+    } else {
+        hour >= from || hour < to
+    }
+}
+
+fn current_hour() -> f64 {
+    let now = Local::now();
+    f64::from(now.hour()) + f64::from(now.minute()) / 60.0
+}
+
+// ===== Backends =====
+
+fn gsettings_set(key: &str, value: &str) -> Result<(), String> {
+    run_command("gsettings", &["set", COLOR_SCHEMA, key, value]).map(|_| ())
+}
+
+// The GNOME keys as they were before night light was switched on here. The
+// file exists exactly while the app is in charge of the keys, so they are
+// still handed back after a restart.
+fn gnome_keys_path() -> PathBuf {
+    crate::get_config_dir().join("night-light-gnome.json")
+}
+
+fn save_gnome_keys() -> Result<(), String> {
+    let keys = GNOME_KEYS
+        .iter()
+        .map(|key| {
+            run_command("gsettings", &["get", COLOR_SCHEMA, key])
+                .map(|value| (key.to_string(), value.trim().to_string()))
+        })
+        .collect::<Result<Vec<(String, String)>, String>>()?;
+    let json = serde_json::to_string_pretty(&keys).map_err(|e| e.to_string())?;
+    fs::create_dir_all(crate::get_config_dir()).map_err(|e| e.to_string())?;
+    fs::write(gnome_keys_path(), json).map_err(|e| e.to_string())
+}
+
+fn restore_gnome_keys() -> Result<(), String> {
+    let Ok(json) = fs::read_to_string(gnome_keys_path()) else {
+        // Never switched on here: the keys belong to the user.
+        return Ok(());
+    };
+    let keys: Vec<(String, String)> = serde_json::from_str(&json)
+        .unwrap_or_else(|_| vec![("night-light-enabled".to_string(), "false".to_string())]);
+    for (key, value) in &keys {
+        gsettings_set(key, value)?;
+    }
+    fs::remove_file(gnome_keys_path()).map_err(|e| e.to_string())
+}
+
+fn apply_gnome(window: Option<(f64, f64)>, temperature: u32) -> Result<(), String> {
+    let Some((from, to)) = window else {
+        return restore_gnome_keys();
+    };
+    if !gnome_keys_path().exists() {
+        save_gnome_keys()?;
+    }
+    gsettings_set("night-light-schedule-automatic", "false")?;
+    gsettings_set("night-light-schedule-from", &format!("{from:.4}"))?;
+    gsettings_set("night-light-schedule-to", &format!("{to:.4}"))?;
+    gsettings_set("night-light-temperature", &format!("uint32 {temperature}"))?;
+    gsettings_set("night-light-enabled", "true")
+}
+
+// The running gamma tool and the temperature it was started with. On Wayland
+// the tool has to keep running to hold the gamma ramps.
+static GAMMA: Mutex<Option<(Child, u32)>> = Mutex::new(None);
+
+// Pid of the running gamma tool, so one left behind by a crash can be
+// stopped on the next start instead of tinting next to a new one.
+fn gamma_pid_path() -> PathBuf {
+    crate::get_cache_dir().join("night-light-gamma.pid")
+}
+
+fn stop_orphaned_gamma(tool: &str) {
+    let path = gamma_pid_path();
+    let Some(pid) = fs::read_to_string(&path)
+        .ok()
+        .and_then(|p| p.trim().parse::<u32>().ok())
+    else {
+        return;
+    };
+    let _ = fs::remove_file(&path);
+    // The pid may have been reused by an unrelated process since.
+    let comm = fs::read_to_string(format!("/proc/{pid}/comm")).unwrap_or_default();
+    if comm.trim() == tool {
+        let _ = run_command("kill", &[&pid.to_string()]);
+        let _ = run_command(tool, &["-x"]);
+    }
+}
+
+fn apply_gamma(tool: &str, active: bool, temperature: u32) -> Result<(), String> {
+    let mut running = GAMMA.lock().map_err(|e| e.to_string())?;
+    if active && running.as_ref().is_some_and(|(_, t)| *t == temperature) {
+        return Ok(());
+    }
+    if let Some((mut child, _)) = running.take() {
+        let _ = child.kill();
+        let _ = child.wait();
+        let _ = fs::remove_file(gamma_pid_path());
+    }
+    if !active {
+        // Restores the gamma ramps on X11, where they outlive the process.
+        let _ = run_command(tool, &["-x"]);
+        return Ok(());
+    }
+    let child = Command::new(tool)
+        .args(["-P", "-O", &temperature.to_string()])
+        .spawn()
+        .map_err(|e| format!("Failed to start {tool}: {e}"))?;
+    let _ = fs::create_dir_all(crate::get_cache_dir());
+    let _ = fs::write(gamma_pid_path(), child.id().to_string());
+    *running = Some((child, temperature));
+    Ok(())
+}
+
+// What was last applied, so the periodic check only touches the backend
+// when something changed.
+#[derive(PartialEq)]
+struct Applied {
+    window: Option<(f64, f64)>,
+    active: bool,
+    temperature: u32,
+}
+
+static APPLIED: Mutex<Option<Applied>> = Mutex::new(None);
+
+fn apply(settings: &DesktopSettings) -> Result<(), String> {
+    let window = night_window(settings);
+    let active = window.is_some_and(|w| in_window(current_hour(), w));
+    let temperature = settings
+        .night_light_temperature
+        .clamp(MIN_TEMPERATURE, MAX_TEMPERATURE);
+
+    let state = Applied {
+        window,
+        active,
+        temperature,
+    };
+    let mut applied = APPLIED.lock().map_err(|e| e.to_string())?;
+    if applied.as_ref() == Some(&state) {
+        return Ok(());
+    }
+    match backend() {
+        Backend::Gnome => apply_gnome(window, temperature)?,
+        Backend::Gamma(tool) => apply_gamma(tool, active, temperature)?,
+        // Refused when switching on; see `save_and_apply`.
+        Backend::None => {}
+    }
+    *applied = Some(state);
+    Ok(())
+}
+
+fn status(settings: &DesktopSettings) -> NightLightStatus {
+    let sun = settings
+        .night_light_latitude
+        .zip(settings.night_light_longitude)
+        .and_then(|(lat, lon)| sun_window(lat, lon))
+        .filter(|(sunset, sunrise)| sunset != sunrise);
+    NightLightStatus {
+        enabled: settings.night_light_enabled,
+        active: night_window(settings).is_some_and(|w| in_window(current_hour(), w)),
+        temperature: settings.night_light_temperature,
+        schedule: settings.night_light_schedule.clone(),
+        from: settings.night_light_from,
+        to: settings.night_light_to,
+        sunset: sun.map(|(sunset, _)| sunset),
+        sunrise: sun.map(|(_, sunrise)| sunrise),
+        backend: backend_name(backend()).to_string(),
+    }
+}
+
+fn save_and_apply(settings: &DesktopSettings) -> Result<NightLightStatus, String> {
+    if settings.night_light_enabled && backend() == Backend::None {
+        return Err(NO_BACKEND.to_string());
+    }
+    save_settings_internal(settings)?;
+    apply(settings)?;
+    Ok(status(settings))
+}
+
+#[tauri::command]
+pub fn get_night_light() -> NightLightStatus {
+    status(&load_settings())
+}
+
+/// Quick toggle; `temperature` is in Kelvin.
+#[tauri::command]
+pub fn set_night_light(
+    enabled: bool,
+    temperature: Option<u32>,
+) -> Result<NightLightStatus, String> {
+    let mut settings = load_settings();
+    settings.night_light_enabled = enabled;
+    if let Some(temperature) = temperature {
+        settings.night_light_temperature = temperature.clamp(MIN_TEMPERATURE, MAX_TEMPERATURE);
+    }
+    save_and_apply(&settings)
+}
+
+/// `schedule` is "manual", "fixed" (uses `from` and `to`) or "sunset"
+/// (uses `latitude` and `longitude`).
+#[tauri::command]
+pub fn set_night_light_schedule(
+    schedule: &str,
+    from: Option<f64>,
+    to: Option<f64>,
+    latitude: Option<f64>,
+    longitude: Option<f64>,
+) -> Result<NightLightStatus, String> {
+    let mut settings = load_settings();
+    for hour in [from, to].into_iter().flatten() {
+        if !(0.0..24.0).contains(&hour) {
+            return Err("Schedule times must be between 0 and 24 hours".to_string());
+        }
+    }
+    if latitude.is_some_and(|lat| !(-90.0..=90.0).contains(&lat))
+        || longitude.is_some_and(|lon| !(-180.0..=180.0).contains(&lon))
+    {
+        return Err("Invalid coordinates".to_string());
+    }
+    settings.night_light_from = from.unwrap_or(settings.night_light_from);
+    settings.night_light_to = to.unwrap_or(settings.night_light_to);
+    settings.night_light_latitude = latitude.or(settings.night_light_latitude);
+    settings.night_light_longitude = longitude.or(settings.night_light_longitude);
+
+    match schedule {
+        "manual" | "fixed" => {}
+        "sunset" => {
+            if settings.night_light_latitude.is_none() || settings.night_light_longitude.is_none() {
+                return Err("The sunset schedule needs a latitude and longitude".to_string());
+            }
+        }
+        other => return Err(format!("Unknown night light schedule: {other}")),
+    }
+    settings.night_light_schedule = schedule.to_string();
+    save_and_apply(&settings)
+}
+
+/// Applies the saved night light state now and whenever the schedule moves
+/// on.
+pub fn start() {
+    std::thread::spawn(|| {
+        if let Backend::Gamma(tool) = backend() {
+            stop_orphaned_gamma(tool);
+        }
+        loop {
+            if let Err(e) = apply(&load_settings()) {
+                log::warn!("Night light: {e}");
+            }
+            std::thread::sleep(CHECK_INTERVAL);
+        }
+    });
+}
+
+/// Stops the gamma tool and restores the screen colors; called on exit. The
+/// GNOME keys stay, as the settings daemon keeps following them.
+pub fn stop() {
+    if let Some(Backend::Gamma(tool)) = BACKEND.get().copied() {
+        if let Err(e) = apply_gamma(tool, false, 0) {
+            log::warn!("Night light: {e}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Days of the year, counted from 0
+    const MARCH_EQUINOX: u32 = 78;
+    const JUNE_SOLSTICE: u32 = 171;
+    const DECEMBER_SOLSTICE: u32 = 354;
+
+    fn assert_near(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 0.25,
+            "{actual} is not near {expected}"
+        );
+    }
+
+    #[test]
+    fn equinox_days_last_twelve_hours() {
+        // Greenwich, in UTC
+        let (sunset, sunrise) = sun_window_on(51.48, 0.0, MARCH_EQUINOX, 0.0).unwrap();
+        assert_near(sunset, 18.2);
+        assert_near(sunrise, 6.0);
+
+        // Same place an hour ahead: both move by the offset.
+        let (sunset, sunrise) = sun_window_on(51.48, 0.0, MARCH_EQUINOX, 60.0).unwrap();
+        assert_near(sunset, 19.2);
+        assert_near(sunrise, 7.0);
+    }
+
+    #[test]
+    fn sunrise_wraps_past_midnight_far_ahead_of_utc() {
+        // Longitude 0 on UTC+14: sunrise is at 20:00 local, sunset at 08:00.
+        let (sunset, sunrise) = sun_window_on(0.0, 0.0, MARCH_EQUINOX, 14.0 * 60.0).unwrap();
+        assert_near(sunset, 8.1);
+        assert_near(sunrise, 20.1);
+        assert!((0.0..24.0).contains(&sunset) && (0.0..24.0).contains(&sunrise));
+    }
+
+    #[test]
+    fn polar_night_and_midnight_sun() {
+        // Svalbard: no sunrise in December, no sunset in June.
+        assert_eq!(
+            sun_window_on(78.22, 15.65, DECEMBER_SOLSTICE, 60.0),
+            Some((0.0, 0.0))
+        );
+        assert_eq!(sun_window_on(78.22, 15.65, JUNE_SOLSTICE, 120.0), None);
+        // The other way round south of the Antarctic circle.
+        assert_eq!(
+            sun_window_on(-77.85, 166.67, DECEMBER_SOLSTICE, 780.0),
+            None
+        );
+    }
+
+    #[test]
+    fn windows_can_wrap_past_midnight() {
+        let evening = (20.0, 7.0);
+        assert!(in_window(20.0, evening));
+        assert!(in_window(23.5, evening));
+        assert!(in_window(0.0, evening));
+        assert!(in_window(6.99, evening));
+        assert!(!in_window(7.0, evening));
+        assert!(!in_window(12.0, evening));
+        assert!(!in_window(19.99, evening));
+
+        let afternoon = (13.0, 17.5);
+        assert!(in_window(13.0, afternoon));
+        assert!(!in_window(17.5, afternoon));
+        assert!(!in_window(2.0, afternoon));
+
+        // from == to: all day, which is also how polar night is expressed.
+        assert!(in_window(0.0, (0.0, 0.0)));
+        assert!(in_window(12.0, (5.0, 5.0)));
+    }
+
+    #[test]
+    fn night_window_follows_the_schedule() {
+        let mut settings = DesktopSettings {
+            night_light_from: 21.5,
+            night_light_to: 6.0,
+            ..Default::default()
+        };
+        assert_eq!(night_window(&settings), None);
+
+        settings.night_light_enabled = true;
+        assert_eq!(night_window(&settings), Some((0.0, 0.0)));
+
+        settings.night_light_schedule = "fixed".to_string();
+        assert_eq!(night_window(&settings), Some((21.5, 6.0)));
+
+        // Without a location there is no sunset to follow.
+        settings.night_light_schedule = "sunset".to_string();
+        assert_eq!(night_window(&settings), None);
+        settings.night_light_latitude = Some(78.22);
+        assert_eq!(night_window(&settings), None);
+        settings.night_light_longitude = Some(15.65);
+        assert_eq!(night_window(&settings), sun_window(78.22, 15.65),);
+    }
+}
@@ -0,0 +1,418 @@
+use std::collections::HashMap;
+use std::net::Ipv6Addr;
+use std::sync::Mutex;
+use zbus::blocking::Connection;
+use zbus::zvariant::{OwnedObjectPath, OwnedValue, Value};
+
+// ===== NetworkManager D-Bus Client =====
+//
+// Typed proxies for the parts of the NetworkManager API the desktop uses.
+// NetworkManager lives on the system bus; setting SIS_CRYSTAL_NM_BUS=session
+// (or to a full D-Bus address) points the client at another bus instead, so a
+// mock service can stand in for NetworkManager during testing.
+
+pub const NM_DEVICE_TYPE_WIFI: u32 = 2;
+pub const NM_DEVICE_TYPE_LOOPBACK: u32 = 32;
+
+pub const NM_DEVICE_STATE_ACTIVATED: u32 = 100;
+
+// NMState: 60 is CONNECTED_SITE, 70 CONNECTED_GLOBAL. CONNECTED_LOCAL (50)
+// means there is no default route, e.g. only a container bridge is up.
+pub const NM_STATE_CONNECTED_SITE: u32 = 60;
+
+// NM80211ApFlags / NM80211ApSecurityFlags
+const AP_FLAGS_PRIVACY: u32 = 0x1;
+const AP_SEC_KEY_MGMT_PSK: u32 = 0x100;
+const AP_SEC_KEY_MGMT_802_1X: u32 = 0x200;
+const AP_SEC_KEY_MGMT_SAE: u32 = 0x400;
+const AP_SEC_KEY_MGMT_OWE: u32 = 0x800;
+
+#[zbus::proxy(
+    interface = "org.freedesktop.NetworkManager",
+    default_service = "org.freedesktop.NetworkManager",
+    default_path = "/org/freedesktop/NetworkManager"
+)]
+pub trait NetworkManager {
+    fn get_devices(&self) -> zbus::Result<Vec<OwnedObjectPath>>;
+
+    fn activate_connection(
+        &self,
+        connection: &zbus::zvariant::ObjectPath<'_>,
+        device: &zbus::zvariant::ObjectPath<'_>,
+        specific_object: &zbus::zvariant::ObjectPath<'_>,
+    ) -> zbus::Result<OwnedObjectPath>;
+
+    fn deactivate_connection(
+        &self,
+        active_connection: &zbus::zvariant::ObjectPath<'_>,
+    ) -> zbus::Result<()>;
+
+    fn add_and_activate_connection(
+        &self,
+        connection: HashMap<&str, HashMap<&str, zbus::zvariant::Value<'_>>>,
+        device: &zbus::zvariant::ObjectPath<'_>,
+        specific_object: &zbus::zvariant::ObjectPath<'_>,
+    ) -> zbus::Result<(OwnedObjectPath, OwnedObjectPath)>;
+
+    fn check_connectivity(&self) -> zbus::Result<u32>;
+
+    #[zbus(property)]
+    fn state(&self) -> zbus::Result<u32>;
+
+    #[zbus(property)]
+    fn active_connections(&self) -> zbus::Result<Vec<OwnedObjectPath>>;
+
+    #[zbus(property)]
+    fn primary_connection(&self) -> zbus::Result<OwnedObjectPath>;
+
+    #[zbus(property)]
+    fn wireless_enabled(&self) -> zbus::Result<bool>;
+
+    #[zbus(property)]
+    fn set_wireless_enabled(&self, value: bool) -> zbus::Result<()>;
+
+    #[zbus(property)]
+    fn wireless_hardware_enabled(&self) -> zbus::Result<bool>;
+
+    #[zbus(property)]
+    fn connectivity(&self) -> zbus::Result<u32>;
+
+    #[zbus(property)]
+    fn connectivity_check_enabled(&self) -> zbus::Result<bool>;
+
+    #[zbus(property)]
+    fn connectivity_check_uri(&self) -> zbus::Result<String>;
+}
+
+#[zbus::proxy(
+    interface = "org.freedesktop.NetworkManager.Device",
+    default_service = "org.freedesktop.NetworkManager"
+)]
+pub trait Device {
+    fn disconnect(&self) -> zbus::Result<()>;
+
+    #[zbus(property)]
+    fn interface(&self) -> zbus::Result<String>;
+
+    #[zbus(property)]
+    fn device_type(&self) -> zbus::Result<u32>;
+
+    #[zbus(property)]
+    fn state(&self) -> zbus::Result<u32>;
+
+    #[zbus(property)]
+    fn hw_address(&self) -> zbus::Result<String>;
+
+    #[zbus(property)]
+    fn active_connection(&self) -> zbus::Result<OwnedObjectPath>;
+
+    #[zbus(property, name = "Ip4Config")]
+    fn ip4_config(&self) -> zbus::Result<OwnedObjectPath>;
+
+    #[zbus(property, name = "Ip6Config")]
+    fn ip6_config(&self) -> zbus::Result<OwnedObjectPath>;
+}
+
+#[zbus::proxy(
+    interface = "org.freedesktop.NetworkManager.Device.Wireless",
+    default_service = "org.freedesktop.NetworkManager"
+)]
+pub trait Wireless {
+    fn get_all_access_points(&self) -> zbus::Result<Vec<OwnedObjectPath>>;
+
+    fn request_scan(&self, options: HashMap<&str, zbus::zvariant::Value<'_>>) -> zbus::Result<()>;
+
+    /// CLOCK_BOOTTIME milliseconds of the last completed scan, -1 if never
+    #[zbus(property)]
+    fn last_scan(&self) -> zbus::Result<i64>;
+
+    #[zbus(property)]
+    fn active_access_point(&self) -> zbus::Result<OwnedObjectPath>;
+}
+
+#[zbus::proxy(
+    interface = "org.freedesktop.NetworkManager.AccessPoint",
+    default_service = "org.freedesktop.NetworkManager"
+)]
+pub trait AccessPoint {
+    #[zbus(property)]
+    fn ssid(&self) -> zbus::Result<Vec<u8>>;
+
+    #[zbus(property)]
+    fn strength(&self) -> zbus::Result<u8>;
+
+    #[zbus(property)]
+    fn frequency(&self) -> zbus::Result<u32>;
+
+    #[zbus(property)]
+    fn hw_address(&self) -> zbus::Result<String>;
+
+    #[zbus(property)]
+    fn flags(&self) -> zbus::Result<u32>;
+
+    #[zbus(property)]
+    fn wpa_flags(&self) -> zbus::Result<u32>;
+
+    #[zbus(property)]
+    fn rsn_flags(&self) -> zbus::Result<u32>;
+}
+
+#[zbus::proxy(
+    interface = "org.freedesktop.NetworkManager.Connection.Active",
+    default_service = "org.freedesktop.NetworkManager"
+)]
+pub trait ActiveConnection {
+    #[zbus(property)]
+    fn id(&self) -> zbus::Result<String>;
+
+    #[zbus(property)]
+    fn uuid(&self) -> zbus::Result<String>;
+
+    #[zbus(property, name = "Type")]
+    fn connection_type(&self) -> zbus::Result<String>;
+
+    /// Path of the Settings.Connection profile this activation uses
+    #[zbus(property)]
+    fn connection(&self) -> zbus::Result<OwnedObjectPath>;
+
+    #[zbus(property)]
+    fn devices(&self) -> zbus::Result<Vec<OwnedObjectPath>>;
+
+    #[zbus(property)]
+    fn state(&self) -> zbus::Result<u32>;
+
+    #[zbus(property)]
+    fn default(&self) -> zbus::Result<bool>;
+
+    #[zbus(property)]
+    fn vpn(&self) -> zbus::Result<bool>;
+}
+
+#[zbus::proxy(
+    interface = "org.freedesktop.NetworkManager.IP4Config",
+    default_service = "org.freedesktop.NetworkManager"
+)]
+pub trait Ip4Config {
+    #[zbus(property)]
+    fn address_data(&self) -> zbus::Result<Vec<HashMap<String, OwnedValue>>>;
+
+    #[zbus(property)]
+    fn gateway(&self) -> zbus::Result<String>;
+
+    #[zbus(property)]
+    fn nameserver_data(&self) -> zbus::Result<Vec<HashMap<String, OwnedValue>>>;
+}
+
+#[zbus::proxy(
+    interface = "org.freedesktop.NetworkManager.IP6Config",
+    default_service = "org.freedesktop.NetworkManager"
+)]
+pub trait Ip6Config {
+    #[zbus(property)]
+    fn address_data(&self) -> zbus::Result<Vec<HashMap<String, OwnedValue>>>;
+
+    #[zbus(property)]
+    fn gateway(&self) -> zbus::Result<String>;
+
+    #[zbus(property)]
+    fn nameservers(&self) -> zbus::Result<Vec<Vec<u8>>>;
+}
+
+/// Connection profile settings as exchanged with NetworkManager (a{sa{sv}}).
+pub type ConnectionSettings = HashMap<String, HashMap<String, OwnedValue>>;
+
+#[zbus::proxy(
+    interface = "org.freedesktop.NetworkManager.Settings",
+    default_service = "org.freedesktop.NetworkManager",
+    default_path = "/org/freedesktop/NetworkManager/Settings"
+)]
+pub trait Settings {
+    fn list_connections(&self) -> zbus::Result<Vec<OwnedObjectPath>>;
+
+    fn get_connection_by_uuid(&self, uuid: &str) -> zbus::Result<OwnedObjectPath>;
+}
+
+#[zbus::proxy(
+    interface = "org.freedesktop.NetworkManager.Settings.Connection",
+    default_service = "org.freedesktop.NetworkManager"
+)]
+pub trait SettingsConnection {
+    fn get_settings(&self) -> zbus::Result<ConnectionSettings>;
+
+    fn get_secrets(&self, setting_name: &str) -> zbus::Result<ConnectionSettings>;
+
+    fn update(&self, properties: ConnectionSettings) -> zbus::Result<()>;
+
+    fn delete(&self) -> zbus::Result<()>;
+}
+
+// Set when SIS_CRYSTAL_NM_BUS points at a bus other than the system bus.
+static OTHER_BUS: Mutex<Option<Connection>> = Mutex::new(None);
+
+/// Shared connection to the bus NetworkManager is reached on.
+pub fn connection() -> Result<Connection, String> {
+    let address = std::env::var("SIS_CRYSTAL_NM_BUS").unwrap_or_default();
+    if address.is_empty() || address == "system" {
+        return crate::bus::system();
+    }
+
+    let mut cached = OTHER_BUS.lock().map_err(|e| e.to_string())?;
+    if let Some(conn) = cached.as_ref() {
+        return Ok(conn.clone());
+    }
+    let conn = if address == "session" {
+        Connection::session()
+    } else {
+        zbus::blocking::connection::Builder::address(address.as_str()).and_then(|b| b.build())
+    }
+    .map_err(|e| format!("Cannot reach NetworkManager: {e}"))?;
+    *cached = Some(conn.clone());
+    Ok(conn)
+}
+
+pub fn manager(conn: &Connection) -> Result<NetworkManagerProxyBlocking<'static>, String> {
+    NetworkManagerProxyBlocking::new(conn).map_err(|e| e.to_string())
+}
+
+pub fn settings(conn: &Connection) -> Result<SettingsProxyBlocking<'static>, String> {
+    SettingsProxyBlocking::new(conn).map_err(|e| e.to_string())
+}
+
+/// Converts a plain value into the owned form used in `ConnectionSettings`.
+pub fn owned<'a>(value: impl Into<Value<'a>>) -> Result<OwnedValue, String> {
+    OwnedValue::try_from(value.into()).map_err(|e| e.to_string())
+}
+
+/// NetworkManager uses "/" for "no object".
+pub fn is_null_path(path: &OwnedObjectPath) -> bool {
+    path.as_str() == "/"
+}
+
+pub fn device_type_name(device_type: u32) -> &'static str {
+    match device_type {
+        1 => "ethernet",
+        2 => "wifi",
+        5 => "bluetooth",
+        8 => "modem",
+        10 => "bond",
+        11 => "vlan",
+        13 => "bridge",
+        14 => "generic",
+        16 => "tun",
+        29 => "wireguard",
+        30 => "wifi-p2p",
+        32 => "loopback",
+        _ => "other",
+    }
+}
+
+pub fn device_state_name(state: u32) -> &'static str {
+    match state {
+        10 => "unmanaged",
+        20 => "unavailable",
+        30 => "disconnected",
+        40..=90 => "connecting",
+        100 => "connected",
+        110 => "disconnecting",
+        120 => "failed",
+        _ => "unknown",
+    }
+}
+
+/// Human readable security summary, e.g. "WPA2 WPA3" or "" for open networks.
+pub fn security_from_flags(flags: u32, wpa_flags: u32, rsn_flags: u32) -> String {
+    let mut parts = Vec::new();
+    if flags & AP_FLAGS_PRIVACY != 0 && wpa_flags == 0 && rsn_flags == 0 {
+        parts.push("WEP");
+    }
+    if wpa_flags != 0 {
+        parts.push("WPA");
+    }
+    if rsn_flags & (AP_SEC_KEY_MGMT_PSK | AP_SEC_KEY_MGMT_802_1X) != 0 {
+        parts.push("WPA2");
+    }
+    if rsn_flags & AP_SEC_KEY_MGMT_SAE != 0 {
+        parts.push("WPA3");
+    }
+    if rsn_flags & AP_SEC_KEY_MGMT_OWE != 0 {
+        parts.push("OWE");
+    }
+    if (wpa_flags | rsn_flags) & AP_SEC_KEY_MGMT_802_1X != 0 {
+        parts.push("802.1X");
+    }
+    parts.join(" ")
+}
+
+/// Addresses, gateway and DNS servers of an IP4Config/IP6Config object.
+#[derive(Default)]
+pub struct IpConfig {
+    pub addresses: Vec<String>,
+    pub gateway: Option<String>,
+    pub dns: Vec<String>,
+}
+
+fn address_list(data: Vec<HashMap<String, OwnedValue>>) -> Vec<String> {
+    data.iter()
+        .filter_map(|entry| {
+            <&str>::try_from(entry.get("address")?)
+                .ok()
+                .map(String::from)
+        })
+        .collect()
+}
+
+pub fn ip4_config(conn: &Connection, path: &OwnedObjectPath) -> IpConfig {
+    if is_null_path(path) {
+        return IpConfig::default();
+    }
+    let Ok(config) = Ip4ConfigProxyBlocking::new(conn, path) else {
+        return IpConfig::default();
+    };
+    IpConfig {
+        addresses: address_list(config.address_data().unwrap_or_default()),
+        gateway: config.gateway().ok().filter(|g| !g.is_empty()),
+        dns: address_list(config.nameserver_data().unwrap_or_default()),
+    }
+}
+
+pub fn ip6_config(conn: &Connection, path: &OwnedObjectPath) -> IpConfig {
+    if is_null_path(path) {
+        return IpConfig::default();
+    }
+    let Ok(config) = Ip6ConfigProxyBlocking::new(conn, path) else {
+        return IpConfig::default();
+    };
+    let dns = config
+        .nameservers()
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|raw| <[u8; 16]>::try_from(raw).ok())
+        .map(|octets| Ipv6Addr::from(octets).to_string())
+        .collect();
+    IpConfig {
+        addresses: address_list(config.address_data().unwrap_or_default()),
+        gateway: config.gateway().ok().filter(|g| !g.is_empty()),
+        dns,
+    }
+}
+
+/// NMConnectivityState as a name.
+pub fn connectivity_name(state: u32) -> &'static str {
+    match state {
+        1 => "none",
+        2 => "portal",
+        3 => "limited",
+        4 => "full",
+        _ => "unknown",
+    }
+}
+
+pub fn active_connection_state_name(state: u32) -> &'static str {
+    match state {
+        1 => "connecting",
+        2 => "connected",
+        3 => "disconnecting",
+        4 => "disconnected",
+        _ => "unknown",
+    }
+}
@@ -0,0 +1,569 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use zbus::blocking::Connection;
+use zbus::fdo;
+use zbus::zvariant::{ObjectPath, OwnedObjectPath, OwnedValue};
+
+use crate::nm::ConnectionSettings;
+
+// ===== Mock NetworkManager =====
+//
+// A stand-in for the parts of the NetworkManager D-Bus API the client uses,
+// served in-process over a peer-to-peer connection so the client can be
+// tested without a system bus. Calls that would change NetworkManager's state
+// are recorded instead, for the tests to inspect.
+
+const NM_PATH: &str = "/org/freedesktop/NetworkManager";
+
+#[derive(Clone, Default)]
+pub struct MockAccessPoint {
+    pub ssid: &'static str,
+    pub strength: u8,
+    pub flags: u32,
+    pub wpa_flags: u32,
+    pub rsn_flags: u32,
+}
+
+#[derive(Clone, Default)]
+pub struct MockDevice {
+    pub interface: &'static str,
+    pub device_type: u32,
+    pub state: u32,
+    /// Only Wi-Fi devices export the Wireless interface.
+    pub access_points: Vec<MockAccessPoint>,
+}
+
+pub struct MockProfile {
+    pub settings: ConnectionSettings,
+    /// Returned by GetSecrets for any setting; `None` makes it fail.
+    pub secrets: Option<ConnectionSettings>,
+}
+
+/// A profile (index into `profiles`) active on a device (index into
+/// `devices`).
+#[derive(Clone, Copy)]
+pub struct MockActive {
+    pub profile: usize,
+    pub device: usize,
+}
+
+#[derive(Default)]
+pub struct MockNm {
+    /// NMState
+    pub state: u32,
+    /// NMConnectivityState
+    pub connectivity: u32,
+    pub devices: Vec<MockDevice>,
+    pub profiles: Vec<MockProfile>,
+    pub active: Vec<MockActive>,
+    /// Index into `active` of the connection holding the default route
+    pub primary: Option<usize>,
+}
+
+/// What the client asked the mock to do.
+#[derive(Default)]
+pub struct Calls {
+    pub added: Vec<ConnectionSettings>,
+    /// (profile, device)
+    pub activated: Vec<(OwnedObjectPath, OwnedObjectPath)>,
+    pub updated: Vec<(OwnedObjectPath, ConnectionSettings)>,
+    pub disconnected: Vec<OwnedObjectPath>,
+}
+
+/// A running mock with the client end of its connection.
+pub struct MockBus {
+    pub conn: Connection,
+    pub calls: Arc<Mutex<Calls>>,
+    _server: Connection,
+    _runtime: tokio::runtime::Runtime,
+}
+
+fn path(path: String) -> OwnedObjectPath {
+    OwnedObjectPath::try_from(path).expect("valid object path")
+}
+
+fn null_path() -> OwnedObjectPath {
+    path("/".to_string())
+}
+
+fn device_path(index: usize) -> OwnedObjectPath {
+    path(format!("{NM_PATH}/Devices/{index}"))
+}
+
+fn access_point_path(device: usize, index: usize) -> OwnedObjectPath {
+    path(format!("{NM_PATH}/AccessPoint/{device}_{index}"))
+}
+
+fn profile_path(index: usize) -> OwnedObjectPath {
+    path(format!("{NM_PATH}/Settings/{index}"))
+}
+
+fn active_path(index: usize) -> OwnedObjectPath {
+    path(format!("{NM_PATH}/ActiveConnection/{index}"))
+}
+
+pub fn clone_settings(settings: &ConnectionSettings) -> ConnectionSettings {
+    settings
+        .iter()
+        .map(|(group, values)| {
+            let values = values
+                .iter()
+                .map(|(key, value)| (key.clone(), value.try_clone().expect("no fds in settings")))
+                .collect();
+            (group.clone(), values)
+        })
+        .collect()
+}
+
+/// Builds profile settings from (group, key, value) triples.
+pub fn settings(entries: Vec<(&str, &str, OwnedValue)>) -> ConnectionSettings {
+    let mut settings = ConnectionSettings::new();
+    for (group, key, value) in entries {
+        settings
+            .entry(group.to_string())
+            .or_default()
+            .insert(key.to_string(), value);
+    }
+    settings
+}
+
+fn setting_str(settings: &ConnectionSettings, group: &str, key: &str) -> String {
+    settings
+        .get(group)
+        .and_then(|g| g.get(key))
+        .and_then(|v| <&str>::try_from(v).ok())
+        .unwrap_or_default()
+        .to_string()
+}
+
+struct Manager {
+    state: u32,
+    connectivity: u32,
+    devices: usize,
+    active: usize,
+    primary: Option<usize>,
+    wireless_enabled: bool,
+    calls: Arc<Mutex<Calls>>,
+}
+
+#[zbus::interface(name = "org.freedesktop.NetworkManager")]
+impl Manager {
+    fn get_devices(&self) -> Vec<OwnedObjectPath> {
+        (0..self.devices).map(device_path).collect()
+    }
+
+    fn activate_connection(
+        &self,
+        connection: ObjectPath<'_>,
+        device: ObjectPath<'_>,
+        _specific_object: ObjectPath<'_>,
+    ) -> OwnedObjectPath {
+        self.calls
+            .lock()
+            .unwrap()
+            .activated
+            .push((connection.into(), device.into()));
+        active_path(self.active)
+    }
+
+    fn deactivate_connection(&self, _active_connection: ObjectPath<'_>) {}
+
+    fn add_and_activate_connection(
+        &self,
+        connection: ConnectionSettings,
+        _device: ObjectPath<'_>,
+        _specific_object: ObjectPath<'_>,
+    ) -> (OwnedObjectPath, OwnedObjectPath) {
+        self.calls.lock().unwrap().added.push(connection);
+        (profile_path(99), active_path(99))
+    }
+
+    fn check_connectivity(&self) -> u32 {
+        self.connectivity
+    }
+
+    #[zbus(property)]
+    fn active_connections(&self) -> Vec<OwnedObjectPath> {
+        (0..self.active).map(active_path).collect()
+    }
+
+    #[zbus(property)]
+    fn primary_connection(&self) -> OwnedObjectPath {
+        self.primary.map(active_path).unwrap_or_else(null_path)
+    }
+
+    #[zbus(property)]
+    fn wireless_enabled(&self) -> bool {
+        self.wireless_enabled
+    }
+
+    #[zbus(property)]
+    fn set_wireless_enabled(&mut self, value: bool) {
+        self.wireless_enabled = value;
+    }
+
+    #[zbus(property)]
+    fn wireless_hardware_enabled(&self) -> bool {
+        true
+    }
+
+    #[zbus(property)]
+    fn connectivity(&self) -> u32 {
+        self.connectivity
+    }
+
+    #[zbus(property)]
+    fn connectivity_check_enabled(&self) -> bool {
+        true
+    }
+
+    #[zbus(property)]
+    fn connectivity_check_uri(&self) -> String {
+        String::new()
+    }
+
+    #[zbus(property)]
+    fn state(&self) -> u32 {
+        self.state
+    }
+}
+
+struct Device {
+    index: usize,
+    device: MockDevice,
+    active: Option<usize>,
+    calls: Arc<Mutex<Calls>>,
+}
+
+#[zbus::interface(name = "org.freedesktop.NetworkManager.Device")]
+impl Device {
+    fn disconnect(&self) {
+        self.calls
+            .lock()
+            .unwrap()
+            .disconnected
+            .push(device_path(self.index));
+    }
+
+    #[zbus(property)]
+    fn interface(&self) -> String {
+        self.device.interface.to_string()
+    }
+
+    #[zbus(property)]
+    fn device_type(&self) -> u32 {
+        self.device.device_type
+    }
+
+    #[zbus(property)]
+    fn state(&self) -> u32 {
+        self.device.state
+    }
+
+    #[zbus(property)]
+    fn hw_address(&self) -> String {
+        String::new()
+    }
+
+    #[zbus(property)]
+    fn active_connection(&self) -> OwnedObjectPath {
+        self.active.map(active_path).unwrap_or_else(null_path)
+    }
+
+    #[zbus(property, name = "Ip4Config")]
+    fn ip4_config(&self) -> OwnedObjectPath {
+        null_path()
+    }
+
+    #[zbus(property, name = "Ip6Config")]
+    fn ip6_config(&self) -> OwnedObjectPath {
+        null_path()
+    }
+}
+
+struct Wireless {
+    index: usize,
+    access_points: usize,
+}
+
+#[zbus::interface(name = "org.freedesktop.NetworkManager.Device.Wireless")]
+impl Wireless {
+    fn get_all_access_points(&self) -> Vec<OwnedObjectPath> {
+        (0..self.access_points)
+            .map(|i| access_point_path(self.index, i))
+            .collect()
+    }
+
+    fn request_scan(&self, _options: HashMap<String, OwnedValue>) {}
+
+    #[zbus(property)]
+    fn last_scan(&self) -> i64 {
+        -1
+    }
+
+    #[zbus(property)]
+    fn active_access_point(&self) -> OwnedObjectPath {
+        null_path()
+    }
+}
+
+struct AccessPoint(MockAccessPoint);
+
+#[zbus::interface(name = "org.freedesktop.NetworkManager.AccessPoint")]
+impl AccessPoint {
+    #[zbus(property)]
+    fn ssid(&self) -> Vec<u8> {
+        self.0.ssid.as_bytes().to_vec()
+    }
+
+    #[zbus(property)]
+    fn strength(&self) -> u8 {
+        self.0.strength
+    }
+
+    #[zbus(property)]
+    fn frequency(&self) -> u32 {
+        2412
+    }
+
+    #[zbus(property)]
+    fn hw_address(&self) -> String {
+        String::new()
+    }
+
+    #[zbus(property)]
+    fn flags(&self) -> u32 {
+        self.0.flags
+    }
+
+    #[zbus(property)]
+    fn wpa_flags(&self) -> u32 {
+        self.0.wpa_flags
+    }
+
+    #[zbus(property)]
+    fn rsn_flags(&self) -> u32 {
+        self.0.rsn_flags
+    }
+}
+
+struct ActiveConnection {
+    id: String,
+    uuid: String,
+    connection_type: String,
+    profile: usize,
+    device: usize,
+    primary: bool,
+}
+
+#[zbus::interface(name = "org.freedesktop.NetworkManager.Connection.Active")]
+impl ActiveConnection {
+    #[zbus(property)]
+    fn id(&self) -> String {
+        self.id.clone()
+    }
+
+    #[zbus(property)]
+    fn uuid(&self) -> String {
+        self.uuid.clone()
+    }
+
+    #[zbus(property, name = "Type")]
+    fn connection_type(&self) -> String {
+        self.connection_type.clone()
+    }
+
+    #[zbus(property)]
+    fn connection(&self) -> OwnedObjectPath {
+        profile_path(self.profile)
+    }
+
+    #[zbus(property)]
+    fn devices(&self) -> Vec<OwnedObjectPath> {
+        vec![device_path(self.device)]
+    }
+
+    #[zbus(property)]
+    fn state(&self) -> u32 {
+        2
+    }
+
+    #[zbus(property)]
+    fn default(&self) -> bool {
+        self.primary
+    }
+
+    #[zbus(property)]
+    fn vpn(&self) -> bool {
+        false
+    }
+}
+
+struct Settings {
+    uuids: Vec<String>,
+}
+
+#[zbus::interface(name = "org.freedesktop.NetworkManager.Settings")]
+impl Settings {
+    fn list_connections(&self) -> Vec<OwnedObjectPath> {
+        (0..self.uuids.len()).map(profile_path).collect()
+    }
+
+    fn get_connection_by_uuid(&self, uuid: &str) -> fdo::Result<OwnedObjectPath> {
+        self.uuids
+            .iter()
+            .position(|u| u == uuid)
+            .map(profile_path)
+            .ok_or_else(|| fdo::Error::Failed(format!("No connection with UUID {uuid}")))
+    }
+}
+
+struct SettingsConnection {
+    index: usize,
+    profile: MockProfile,
+    calls: Arc<Mutex<Calls>>,
+}
+
+#[zbus::interface(name = "org.freedesktop.NetworkManager.Settings.Connection")]
+impl SettingsConnection {
+    fn get_settings(&self) -> ConnectionSettings {
+        clone_settings(&self.profile.settings)
+    }
+
+    fn get_secrets(&self, setting_name: &str) -> fdo::Result<ConnectionSettings> {
+        let mut secrets = self
+            .profile
+            .secrets
+            .as_ref()
+            .map(clone_settings)
+            .ok_or_else(|| fdo::Error::AccessDenied("Not authorized".to_string()))?;
+        Ok(secrets
+            .remove(setting_name)
+            .map(|group| HashMap::from([(setting_name.to_string(), group)]))
+            .unwrap_or_default())
+    }
+
+    fn update(&self, properties: ConnectionSettings) {
+        self.calls
+            .lock()
+            .unwrap()
+            .updated
+            .push((profile_path(self.index), properties));
+    }
+
+    fn delete(&self) {}
+}
+
+impl MockNm {
+    /// Serves the mock and connects a client to it.
+    pub fn serve(self) -> MockBus {
+        let runtime = tokio::runtime::Runtime::new().expect("tokio runtime");
+        let _guard = runtime.enter();
+        let (client, server) = tokio::net::UnixStream::pair().expect("socket pair");
+        let calls = Arc::new(Mutex::new(Calls::default()));
+
+        let guid = zbus::Guid::generate();
+        let mut builder = zbus::blocking::connection::Builder::unix_stream(server)
+            .server(guid)
+            .expect("server guid")
+            .p2p()
+            .serve_at(
+                NM_PATH,
+                Manager {
+                    state: self.state,
+                    connectivity: self.connectivity,
+                    devices: self.devices.len(),
+                    active: self.active.len(),
+                    primary: self.primary,
+                    wireless_enabled: true,
+                    calls: calls.clone(),
+                },
+            )
+            .expect("manager");
+
+        for (index, device) in self.devices.iter().enumerate() {
+            let active = self.active.iter().position(|a| a.device == index);
+            if device.device_type == crate::nm::NM_DEVICE_TYPE_WIFI {
+                builder = builder
+                    .serve_at(
+                        device_path(index),
+                        Wireless {
+                            index,
+                            access_points: device.access_points.len(),
+                        },
+                    )
+                    .expect("wireless");
+                for (ap, access_point) in device.access_points.iter().enumerate() {
+                    builder = builder
+                        .serve_at(
+                            access_point_path(index, ap),
+                            AccessPoint(access_point.clone()),
+                        )
+                        .expect("access point");
+                }
+            }
+            builder = builder
+                .serve_at(
+                    device_path(index),
+                    Device {
+                        index,
+                        device: device.clone(),
+                        active,
+                        calls: calls.clone(),
+                    },
+                )
+                .expect("device");
+        }
+
+        for (index, active) in self.active.iter().enumerate() {
+            let settings = &self.profiles[active.profile].settings;
+            builder = builder
+                .serve_at(
+                    active_path(index),
+                    ActiveConnection {
+                        id: setting_str(settings, "connection", "id"),
+                        uuid: setting_str(settings, "connection", "uuid"),
+                        connection_type: setting_str(settings, "connection", "type"),
+                        profile: active.profile,
+                        device: active.device,
+                        primary: self.primary == Some(index),
+                    },
+                )
+                .expect("active connection");
+        }
+
+        let uuids = self
+            .profiles
+            .iter()
+            .map(|p| setting_str(&p.settings, "connection", "uuid"))
+            .collect();
+        builder = builder
+            .serve_at(format!("{NM_PATH}/Settings"), Settings { uuids })
+            .expect("settings");
+        for (index, profile) in self.profiles.into_iter().enumerate() {
+            builder = builder
+                .serve_at(
+                    profile_path(index),
+                    SettingsConnection {
+                        index,
+                        profile,
+                        calls: calls.clone(),
+                    },
+                )
+                .expect("profile");
+        }
+
+        // Both ends authenticate with each other, so they are built in parallel.
+        let server = std::thread::spawn(move || builder.build().expect("mock server"));
+        let conn = zbus::blocking::connection::Builder::unix_stream(client)
+            .p2p()
+            .build()
+            .expect("mock client");
+        MockBus {
+            conn,
+            calls,
+            _server: server.join().expect("mock server thread"),
+            _runtime: runtime,
+        }
+    }
+}
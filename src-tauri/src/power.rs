@@ -0,0 +1,442 @@
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::bus::{self, prop, Properties};
+use crate::upower::{self, DEVICE_TYPE_BATTERY, DEVICE_TYPE_LINE_POWER, DEVICE_TYPE_UPS};
+use crate::{load_settings, run_command, save_settings_internal, BatteryInfo};
+
+// ===== Power Supply =====
+//
+// Batteries, UPS units and AC adapters as reported by UPower. Without UPower
+// the `battery` crate and /sys/class/power_supply are read directly, which
+// gives the same data minus UPS units.
+
+#[derive(Serialize, Clone)]
+pub struct BatteryDevice {
+    /// Kernel name such as "BAT0", or UPower's native path for UPS units
+    pub id: String,
+    /// "battery" or "ups"
+    pub kind: String,
+    pub vendor: Option<String>,
+    pub model: Option<String>,
+    pub serial: Option<String>,
+    /// e.g. "lithium-ion"
+    pub technology: String,
+    pub percentage: f32,
+    /// "charging", "discharging", "full", "empty", "pending-charge",
+    /// "pending-discharge" or "unknown"
+    pub state: String,
+    pub is_charging: bool,
+    /// Watt-hours
+    pub energy: Option<f32>,
+    pub energy_full: Option<f32>,
+    pub energy_full_design: Option<f32>,
+    /// Remaining capacity compared to the design capacity, in percent
+    pub health: Option<f32>,
+    /// Charge or discharge rate in watts
+    pub power: Option<f32>,
+    /// Volts
+    pub voltage: Option<f32>,
+    pub cycle_count: Option<u32>,
+    /// Seconds
+    pub time_to_full: Option<u64>,
+    pub time_to_empty: Option<u64>,
+}
+
+#[derive(Serialize, Clone)]
+pub struct PowerStatus {
+    /// The system batteries combined; `None` on machines without one
+    pub aggregate: Option<BatteryInfo>,
+    pub batteries: Vec<BatteryDevice>,
+    /// `None` when there is no AC adapter to report on (desktops)
+    pub ac_online: Option<bool>,
+    pub on_battery: bool,
+}
+
+fn non_empty(value: Option<String>) -> Option<String> {
+    value
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+}
+
+fn positive(value: f32) -> Option<f32> {
+    (value.is_finite() && value > 0.0).then_some(value)
+}
+
+fn upower_battery(props: &Properties, kind: &str) -> BatteryDevice {
+    let float = |key: &str| prop::<f64>(props, key).and_then(|v| positive(v as f32));
+    let seconds = |key: &str| prop::<i64>(props, key).filter(|s| *s > 0).map(|s| s as u64);
+    let state = prop::<u32>(props, "State").unwrap_or(0);
+    BatteryDevice {
+        id: prop(props, "NativePath").unwrap_or_default(),
+        kind: kind.to_string(),
+        vendor: non_empty(prop(props, "Vendor")),
+        model: non_empty(prop(props, "Model")),
+        serial: non_empty(prop(props, "Serial")),
+        technology: upower::technology_name(prop(props, "Technology").unwrap_or(0)).to_string(),
+        percentage: prop::<f64>(props, "Percentage").unwrap_or(0.0) as f32,
+        state: upower::state_name(state).to_string(),
+        is_charging: state == upower::DEVICE_STATE_CHARGING,
+        energy: float("Energy"),
+        energy_full: float("EnergyFull"),
+        energy_full_design: float("EnergyFullDesign"),
+        health: float("Capacity"),
+        power: float("EnergyRate"),
+        voltage: float("Voltage"),
+        // -1 or 0 when the battery does not report it
+        cycle_count: prop::<i32>(props, "ChargeCycles")
+            .filter(|c| *c > 0)
+            .map(|c| c as u32),
+        time_to_full: seconds("TimeToFull"),
+        time_to_empty: seconds("TimeToEmpty"),
+    }
+}
+
+fn upower_status() -> Result<PowerStatus, String> {
+    let conn = bus::system()?;
+    let mut batteries = Vec::new();
+    let mut ac_online: Option<bool> = None;
+    for path in upower::device_paths(&conn)? {
+        let Ok(props) = upower::device_properties(&conn, &path) else {
+            continue;
+        };
+        // Empty battery bays and unplugged UPS units are still listed.
+        let present = prop(&props, "IsPresent").unwrap_or(false);
+        match prop::<u32>(&props, "Type").unwrap_or(0) {
+            DEVICE_TYPE_LINE_POWER => {
+                let online = prop(&props, "Online").unwrap_or(false);
+                ac_online = Some(ac_online.unwrap_or(false) || online);
+            }
+            // Peripheral batteries (mice, headsets) have PowerSupply unset.
+            DEVICE_TYPE_BATTERY if present && prop(&props, "PowerSupply").unwrap_or(false) => {
+                batteries.push(upower_battery(&props, "battery"));
+            }
+            DEVICE_TYPE_UPS if present => batteries.push(upower_battery(&props, "ups")),
+            _ => {}
+        }
+    }
+    batteries.sort_by(|a, b| a.kind.cmp(&b.kind).then(a.id.cmp(&b.id)));
+
+    let on_battery = upower::upower(&conn)?
+        .on_battery()
+        .unwrap_or(ac_online == Some(false));
+    Ok(PowerStatus {
+        aggregate: aggregate(&batteries),
+        batteries,
+        ac_online,
+        on_battery,
+    })
+}
+
+/// Whether a mains adapter is plugged in, from /sys/class/power_supply.
+fn sysfs_ac_online() -> Option<bool> {
+    let mut found = None;
+    for entry in fs::read_dir("/sys/class/power_supply").ok()?.flatten() {
+        let path = entry.path();
+        let kind = fs::read_to_string(path.join("type")).unwrap_or_default();
+        if kind.trim() != "Mains" {
+            continue;
+        }
+        let online = fs::read_to_string(path.join("online")).is_ok_and(|s| s.trim() == "1");
+        found = Some(found.unwrap_or(false) || online);
+    }
+    found
+}
+
+fn fallback_status() -> PowerStatus {
+    use battery::Manager;
+
+    // The battery crate reports energy in joules.
+    const JOULES_PER_WH: f32 = 3600.0;
+
+    let found: Vec<battery::Battery> = match Manager::new() {
+        Ok(manager) => manager
+            .batteries()
+            .map(|batteries| batteries.flatten().collect())
+            .unwrap_or_default(),
+        Err(_) => Vec::new(),
+    };
+    let batteries: Vec<BatteryDevice> = found
+        .into_iter()
+        .enumerate()
+        .map(|(index, battery)| {
+            let state = battery.state();
+            BatteryDevice {
+                id: format!("battery{index}"),
+                kind: "battery".to_string(),
+                vendor: non_empty(battery.vendor().map(str::to_string)),
+                model: non_empty(battery.model().map(str::to_string)),
+                serial: non_empty(battery.serial_number().map(str::to_string)),
+                technology: battery.technology().to_string(),
+                percentage: battery.state_of_charge().value * 100.0,
+                state: state.to_string(),
+                is_charging: state == battery::State::Charging,
+                energy: positive(battery.energy().value / JOULES_PER_WH),
+                energy_full: positive(battery.energy_full().value / JOULES_PER_WH),
+                energy_full_design: positive(battery.energy_full_design().value / JOULES_PER_WH),
+                health: positive(battery.state_of_health().value * 100.0),
+                power: positive(battery.energy_rate().value),
+                voltage: positive(battery.voltage().value),
+                cycle_count: battery.cycle_count(),
+                time_to_full: battery.time_to_full().map(|t| t.value as u64),
+                time_to_empty: battery.time_to_empty().map(|t| t.value as u64),
+            }
+        })
+        .collect();
+
+    let ac_online = sysfs_ac_online();
+    let on_battery = match ac_online {
+        Some(online) => !online,
+        None => batteries.iter().any(|b| b.state == "discharging"),
+    };
+    PowerStatus {
+        aggregate: aggregate(&batteries),
+        batteries,
+        ac_online,
+        on_battery,
+    }
+}
+
+/// Combines the system batteries the way UPower's display device does:
+/// charge weighted by capacity, rates added up. UPS units only count when
+/// there is no internal battery.
+fn aggregate(batteries: &[BatteryDevice]) -> Option<BatteryInfo> {
+    let internal: Vec<&BatteryDevice> = batteries.iter().filter(|b| b.kind == "battery").collect();
+    let system = if internal.is_empty() {
+        batteries.iter().collect()
+    } else {
+        internal
+    };
+    if system.is_empty() {
+        return None;
+    }
+
+    let energy: Option<f32> = system.iter().map(|b| b.energy).sum();
+    let energy_full: Option<f32> = system.iter().map(|b| b.energy_full).sum();
+    let power: Option<f32> = system.iter().map(|b| b.power).sum();
+    let is_charging = system.iter().any(|b| b.is_charging);
+    let discharging = system.iter().any(|b| b.state == "discharging");
+
+    let percentage = match (energy, energy_full) {
+        (Some(energy), Some(full)) if full > 0.0 => (energy / full * 100.0).min(100.0),
+        _ => system.iter().map(|b| b.percentage).sum::<f32>() / system.len() as f32,
+    };
+    let seconds_for = |wh: f32| match power {
+        Some(watts) if watts > 0.0 => Some((wh / watts * 3600.0) as u64),
+        _ => None,
+    };
+    let time_to_full = if is_charging {
+        energy
+            .zip(energy_full)
+            .and_then(|(energy, full)| seconds_for(full - energy))
+            .or_else(|| system.iter().filter_map(|b| b.time_to_full).max())
+    } else {
+        None
+    };
+    let time_to_empty = if discharging {
+        energy
+            .and_then(seconds_for)
+            .or_else(|| system.iter().filter_map(|b| b.time_to_empty).max())
+    } else {
+        None
+    };
+
+    Some(BatteryInfo {
+        percentage,
+        is_charging,
+        time_to_full,
+        time_to_empty,
+        power,
+    })
+}
+
+/// Every battery and UPS with an aggregate of the system batteries.
+#[tauri::command]
+pub fn get_battery_info() -> PowerStatus {
+    upower_status().unwrap_or_else(|_| fallback_status())
+}
+
+// ===== Charge Thresholds =====
+//
+// Laptops that support it (ThinkPad, ASUS, some Dell and Huawei models) expose
+// charge_control_{start,end}_threshold under /sys/class/power_supply/BAT*.
+// The files are root-owned, so writes fall back to pkexec. The desired values
+// are kept in DesktopSettings because many firmwares forget them on power loss.
+
+const POWER_SUPPLY_DIR: &str = "/sys/class/power_supply";
+
+#[derive(Serialize, Clone)]
+pub struct ChargeThresholds {
+    /// e.g. "BAT0"
+    pub battery: String,
+    /// Charging starts below this percentage
+    pub start: Option<u8>,
+    /// Charging stops at this percentage
+    pub end: Option<u8>,
+    pub start_supported: bool,
+    pub end_supported: bool,
+}
+
+/// The threshold file of a battery, preferring the generic name over the
+/// older ThinkPad one.
+fn threshold_file(battery: &Path, names: [&str; 2]) -> Option<PathBuf> {
+    names
+        .into_iter()
+        .map(|name| battery.join(name))
+        .find(|path| path.exists())
+}
+
+fn start_file(battery: &Path) -> Option<PathBuf> {
+    threshold_file(
+        battery,
+        ["charge_control_start_threshold", "charge_start_threshold"],
+    )
+}
+
+fn end_file(battery: &Path) -> Option<PathBuf> {
+    threshold_file(
+        battery,
+        ["charge_control_end_threshold", "charge_stop_threshold"],
+    )
+}
+
+fn read_threshold(path: Option<&PathBuf>) -> Option<u8> {
+    fs::read_to_string(path?).ok()?.trim().parse().ok()
+}
+
+/// Batteries with at least one threshold file.
+fn threshold_batteries() -> Vec<PathBuf> {
+    let mut batteries: Vec<PathBuf> = fs::read_dir(POWER_SUPPLY_DIR)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            fs::read_to_string(path.join("type")).is_ok_and(|t| t.trim() == "Battery")
+                && (start_file(path).is_some() || end_file(path).is_some())
+        })
+        .collect();
+    batteries.sort();
+    batteries
+}
+
+fn charge_thresholds(battery: &Path) -> ChargeThresholds {
+    let start = start_file(battery);
+    let end = end_file(battery);
+    ChargeThresholds {
+        battery: battery
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        start: read_threshold(start.as_ref()),
+        end: read_threshold(end.as_ref()),
+        start_supported: start.is_some(),
+        end_supported: end.is_some(),
+    }
+}
+
+/// Writes sysfs values in order, asking for authorization through pkexec
+/// once if the files are not writable by the user.
+fn write_sysfs(writes: &[(PathBuf, u8)]) -> Result<(), String> {
+    let mut pending = writes
+        .iter()
+        .skip_while(|(path, value)| fs::write(path, value.to_string()).is_ok());
+    let Some(first) = pending.next() else {
+        return Ok(());
+    };
+    // Paths come from read_dir on POWER_SUPPLY_DIR, but quote them anyway.
+    let script = std::iter::once(first)
+        .chain(pending)
+        .map(|(path, value)| {
+            format!(
+                "echo {value} > '{}'",
+                path.to_string_lossy().replace('\'', "'\\''")
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(" && ");
+    run_command("pkexec", &["sh", "-c", &script])
+        .map(|_| ())
+        .map_err(|e| format!("Failed to set charge thresholds: {}", e.trim()))
+}
+
+/// Applies thresholds to every battery that supports them. Unsupported
+/// values are skipped (ASUS laptops only have an end threshold).
+fn apply_charge_thresholds(start: Option<u8>, end: Option<u8>) -> Result<(), String> {
+    let mut writes = Vec::new();
+    for battery in threshold_batteries() {
+        let start_path = start_file(&battery);
+        let end_path = end_file(&battery);
+        let current_start = read_threshold(start_path.as_ref());
+        let current_end = read_threshold(end_path.as_ref());
+
+        let start_write = start_path.zip(start).filter(|_| start != current_start);
+        let end_write = end_path.zip(end).filter(|_| end != current_end);
+        // The kernel rejects a start at or above the current end, so raise the
+        // end first in that case.
+        let end_first = start.zip(current_end).is_some_and(|(s, e)| s >= e);
+        if end_first {
+            writes.extend(end_write);
+            writes.extend(start_write);
+        } else {
+            writes.extend(start_write);
+            writes.extend(end_write);
+        }
+    }
+    write_sysfs(&writes)
+}
+
+#[tauri::command]
+pub fn get_charge_thresholds() -> Vec<ChargeThresholds> {
+    threshold_batteries()
+        .iter()
+        .map(|battery| charge_thresholds(battery))
+        .collect()
+}
+
+/// Sets the thresholds on every supported battery and remembers them so they
+/// are restored at startup. With both values `None` the saved thresholds are
+/// cleared and the current ones are left as they are.
+#[tauri::command]
+pub fn set_charge_thresholds(
+    start: Option<u8>,
+    end: Option<u8>,
+) -> Result<Vec<ChargeThresholds>, String> {
+    if let Some(end) = end {
+        if !(1..=100).contains(&end) {
+            return Err("End threshold must be between 1 and 100".to_string());
+        }
+    }
+    if let Some(start) = start {
+        if start >= end.unwrap_or(100) {
+            return Err("Start threshold must be below the end threshold".to_string());
+        }
+    }
+    if (start.is_some() || end.is_some()) && threshold_batteries().is_empty() {
+        return Err("This laptop does not support charge thresholds".to_string());
+    }
+
+    apply_charge_thresholds(start, end)?;
+    let mut settings = load_settings();
+    settings.charge_start_threshold = start;
+    settings.charge_end_threshold = end;
+    save_settings_internal(&settings)?;
+    Ok(get_charge_thresholds())
+}
+
+/// Re-applies the saved thresholds if the firmware lost them.
+pub fn restore_charge_thresholds() {
+    let settings = load_settings();
+    if settings.charge_start_threshold.is_none() && settings.charge_end_threshold.is_none() {
+        return;
+    }
+    std::thread::spawn(move || {
+        if let Err(e) = apply_charge_thresholds(
+            settings.charge_start_threshold,
+            settings.charge_end_threshold,
+        ) {
+            log::warn!("Failed to restore charge thresholds: {e}");
+        }
+    });
+}
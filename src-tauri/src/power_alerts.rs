@@ -0,0 +1,217 @@
+use serde::Serialize;
+use std::fs;
+use std::time::{Duration, SystemTime};
+use tauri::{AppHandle, Emitter};
+use tauri_plugin_notification::NotificationExt;
+
+use crate::power::{self, PowerStatus};
+use crate::DesktopSettings;
+
+// ===== Battery Alerts =====
+//
+// Watches the system battery and notifies when the charger is plugged in or
+// out and when the charge drops below one of the configured warning levels.
+// At the critical level the configured action runs through `system_action`
+// after a short grace period, unless the charger is connected in between. It
+// runs again after a resume if the battery is still critical.
+// Every alert is also emitted as a `power://battery-alert` event.
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(5);
+const CRITICAL_GRACE: Duration = Duration::from_secs(60);
+// A longer gap between two checks means the machine was asleep.
+const RESUME_GAP: Duration = Duration::from_secs(30);
+
+/// Values accepted for `battery_critical_action`.
+const CRITICAL_ACTIONS: &[&str] = &["none", "suspend", "hibernate", "shutdown"];
+
+#[derive(Serialize, Clone)]
+pub struct BatteryAlert {
+    /// "plugged", "unplugged", "low" or "critical"
+    pub kind: String,
+    pub percentage: f32,
+    /// The warning level that was crossed, for "low"
+    pub level: Option<u8>,
+    /// What will happen at "critical", e.g. "suspend"
+    pub action: Option<String>,
+}
+
+#[derive(Default)]
+struct AlertState {
+    on_battery: Option<bool>,
+    // Lowest warning level already notified on this discharge
+    warned: Option<u8>,
+    critical_handled: bool,
+    last_check: Option<SystemTime>,
+    // Settings as of the settings file's modification time, so they are only
+    // parsed again after a change
+    settings: Option<(Option<SystemTime>, DesktopSettings)>,
+}
+
+impl AlertState {
+    fn settings(&mut self) -> &DesktopSettings {
+        let modified = fs::metadata(crate::get_settings_path())
+            .and_then(|m| m.modified())
+            .ok();
+        if self
+            .settings
+            .as_ref()
+            .is_none_or(|(cached, _)| modified.is_none() || *cached != modified)
+        {
+            self.settings = Some((modified, crate::load_settings()));
+        }
+        &self.settings.as_ref().expect("settings were just loaded").1
+    }
+}
+
+fn format_remaining(seconds: Option<u64>) -> String {
+    match seconds {
+        Some(s) if s >= 3600 => format!(", about {}h {}m left", s / 3600, s / 60 % 60),
+        Some(s) if s >= 60 => format!(", about {} min left", s / 60),
+        _ => String::new(),
+    }
+}
+
+fn alert(app: &AppHandle, alert: BatteryAlert, title: &str, body: &str) {
+    if let Err(e) = app.notification().builder().title(title).body(body).show() {
+        log::warn!("Failed to show battery notification: {e}");
+    }
+    let _ = app.emit("power://battery-alert", alert);
+}
+
+/// Runs the critical action unless the charger is connected during the grace
+/// period.
+fn run_critical_action(action: &str) {
+    std::thread::sleep(CRITICAL_GRACE);
+    if !power::get_battery_info().on_battery {
+        return;
+    }
+    if let Err(e) = crate::system_action(action) {
+        log::error!("Critical battery action '{action}' failed: {e}");
+    }
+}
+
+fn check(app: &AppHandle, state: &mut AlertState, status: &PowerStatus) {
+    let now = SystemTime::now();
+    let resumed = state
+        .last_check
+        .and_then(|last| now.duration_since(last).ok())
+        .is_some_and(|gap| gap > RESUME_GAP);
+    state.last_check = Some(now);
+
+    let Some(battery) = status.aggregate.as_ref() else {
+        return;
+    };
+    let settings = state.settings().clone();
+    let percentage = battery.percentage;
+
+    if let Some(was_on_battery) = state.on_battery {
+        if was_on_battery != status.on_battery && settings.power_notifications {
+            let (kind, title) = if status.on_battery {
+                ("unplugged", "Running on battery")
+            } else {
+                ("plugged", "Charger connected")
+            };
+            let body = if status.on_battery {
+                format!(
+                    "{percentage:.0}% remaining{}",
+                    format_remaining(battery.time_to_empty)
+                )
+            } else {
+                format!("{percentage:.0}% charged")
+            };
+            alert(
+                app,
+                BatteryAlert {
+                    kind: kind.to_string(),
+                    percentage,
+                    level: None,
+                    action: None,
+                },
+                title,
+                &body,
+            );
+        }
+    }
+    state.on_battery = Some(status.on_battery);
+
+    if !status.on_battery {
+        state.warned = None;
+        state.critical_handled = false;
+        return;
+    }
+
+    let critical = percentage <= f32::from(settings.battery_critical_level);
+    // Handle the critical level again once the charge recovered above it, or
+    // after a resume (the critical action may have been the suspend).
+    if !critical || resumed {
+        state.critical_handled = false;
+    }
+    if critical && !state.critical_handled {
+        state.critical_handled = true;
+        // Nothing below the critical level is worth another warning.
+        state.warned = Some(0);
+        let action = settings.battery_critical_action.clone();
+        let runs_action = action != "none" && CRITICAL_ACTIONS.contains(&action.as_str());
+        let body = if runs_action {
+            format!(
+                "{percentage:.0}% remaining. The computer will {action} in {} seconds unless it is plugged in.",
+                CRITICAL_GRACE.as_secs()
+            )
+        } else {
+            format!("{percentage:.0}% remaining. Plug in the charger now.")
+        };
+        alert(
+            app,
+            BatteryAlert {
+                kind: "critical".to_string(),
+                percentage,
+                level: Some(settings.battery_critical_level),
+                action: runs_action.then(|| action.clone()),
+            },
+            "Battery critically low",
+            &body,
+        );
+        if runs_action {
+            std::thread::spawn(move || run_critical_action(&action));
+        }
+        return;
+    }
+
+    let level = settings
+        .battery_warning_levels
+        .iter()
+        .copied()
+        .filter(|level| percentage <= f32::from(*level))
+        .min();
+    if let Some(level) = level {
+        if state.warned.is_none_or(|warned| level < warned) {
+            state.warned = Some(level);
+            alert(
+                app,
+                BatteryAlert {
+                    kind: "low".to_string(),
+                    percentage,
+                    level: Some(level),
+                    action: None,
+                },
+                "Battery low",
+                &format!(
+                    "{percentage:.0}% remaining{}",
+                    format_remaining(battery.time_to_empty)
+                ),
+            );
+        }
+    }
+}
+
+/// Starts the battery alert watcher.
+pub fn start(app: &AppHandle) {
+    let app = app.clone();
+    std::thread::spawn(move || {
+        let mut state = AlertState::default();
+        loop {
+            check(&app, &mut state, &power::get_battery_info());
+            std::thread::sleep(CHECK_INTERVAL);
+        }
+    });
+}
@@ -0,0 +1,166 @@
+use serde::Serialize;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use zbus::blocking::{Connection, MessageIterator};
+use zbus::MatchRule;
+
+use crate::bus::{self, prop, Properties};
+
+// ===== Power Profiles =====
+//
+// Reads and switches the power profile through power-profiles-daemon. Changes
+// made here or anywhere else (GNOME Settings, powerprofilesctl) are pushed to
+// the UI as `power://profiles-changed` events.
+
+const SERVICE: &str = "net.hadess.PowerProfiles";
+
+#[zbus::proxy(
+    interface = "net.hadess.PowerProfiles",
+    default_service = "net.hadess.PowerProfiles",
+    default_path = "/net/hadess/PowerProfiles"
+)]
+trait PowerProfilesDaemon {
+    #[zbus(property)]
+    fn active_profile(&self) -> zbus::Result<String>;
+
+    #[zbus(property)]
+    fn set_active_profile(&self, value: &str) -> zbus::Result<()>;
+
+    #[zbus(property)]
+    fn performance_degraded(&self) -> zbus::Result<String>;
+
+    #[zbus(property)]
+    fn profiles(&self) -> zbus::Result<Vec<Properties>>;
+
+    #[zbus(property)]
+    fn active_profile_holds(&self) -> zbus::Result<Vec<Properties>>;
+}
+
+#[derive(Serialize, Clone)]
+pub struct PowerProfile {
+    /// "power-saver", "balanced" or "performance"
+    pub name: String,
+    /// e.g. "intel_pstate", "amd_pstate" or "placeholder"
+    pub driver: Option<String>,
+}
+
+/// An application asking for a profile, e.g. a game holding "performance".
+#[derive(Serialize, Clone)]
+pub struct ProfileHold {
+    pub profile: String,
+    pub reason: String,
+    pub application_id: String,
+}
+
+#[derive(Serialize, Clone)]
+pub struct PowerProfiles {
+    pub active: String,
+    /// Available profiles, lowest power first
+    pub profiles: Vec<PowerProfile>,
+    /// Why "performance" is currently throttled, e.g. "lap-detected" or
+    /// "high-operating-temperature"
+    pub degraded: Option<String>,
+    pub holds: Vec<ProfileHold>,
+}
+
+fn daemon(conn: &Connection) -> Result<PowerProfilesDaemonProxyBlocking<'static>, String> {
+    PowerProfilesDaemonProxyBlocking::new(conn).map_err(|e| e.to_string())
+}
+
+fn profile_rank(name: &str) -> u8 {
+    match name {
+        "power-saver" => 0,
+        "balanced" => 1,
+        "performance" => 2,
+        _ => 3,
+    }
+}
+
+fn read_profiles(conn: &Connection) -> Result<PowerProfiles, String> {
+    let daemon = daemon(conn)?;
+    let active = daemon
+        .active_profile()
+        .map_err(|e| format!("power-profiles-daemon is not available: {e}"))?;
+
+    let mut profiles: Vec<PowerProfile> = daemon
+        .profiles()
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|p| {
+            Some(PowerProfile {
+                name: prop(p, "Profile")?,
+                driver: prop::<String>(p, "Driver").filter(|d| !d.is_empty()),
+            })
+        })
+        .collect();
+    profiles.sort_by_key(|p| profile_rank(&p.name));
+
+    let holds = daemon
+        .active_profile_holds()
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|h| {
+            Some(ProfileHold {
+                profile: prop(h, "Profile")?,
+                reason: prop(h, "Reason").unwrap_or_default(),
+                application_id: prop(h, "ApplicationId").unwrap_or_default(),
+            })
+        })
+        .collect();
+
+    Ok(PowerProfiles {
+        active,
+        profiles,
+        degraded: daemon.performance_degraded().ok().filter(|d| !d.is_empty()),
+        holds,
+    })
+}
+
+#[tauri::command]
+pub fn get_power_profiles() -> Result<PowerProfiles, String> {
+    let conn = bus::system()?;
+    read_profiles(&conn)
+}
+
+#[tauri::command]
+pub fn set_power_profile(profile: &str) -> Result<(), String> {
+    let conn = bus::system()?;
+    let current = read_profiles(&conn)?;
+    if !current.profiles.iter().any(|p| p.name == profile) {
+        return Err(format!("Power profile not available: {profile}"));
+    }
+    daemon(&conn)?
+        .set_active_profile(profile)
+        .map_err(|e| e.to_string())
+}
+
+fn listen(app: &AppHandle) -> Result<(), String> {
+    let conn = bus::system()?;
+    let rule = MatchRule::builder()
+        .msg_type(zbus::message::Type::Signal)
+        .sender(SERVICE)
+        .and_then(|b| b.interface("org.freedesktop.DBus.Properties"))
+        .and_then(|b| b.member("PropertiesChanged"))
+        .map_err(|e| e.to_string())?
+        .build();
+    let signals =
+        MessageIterator::for_match_rule(rule, &conn, Some(16)).map_err(|e| e.to_string())?;
+
+    for _ in signals.flatten() {
+        if let Ok(profiles) = read_profiles(&conn) {
+            let _ = app.emit("power://profiles-changed", profiles);
+        }
+    }
+    Err("power-profiles-daemon signal stream closed".to_string())
+}
+
+/// Starts forwarding power profile changes to the UI.
+pub fn start(app: &AppHandle) {
+    let app = app.clone();
+    std::thread::spawn(move || loop {
+        if let Err(e) = listen(&app) {
+            log::warn!("Power profile listener stopped: {e}");
+        }
+        std::thread::sleep(Duration::from_secs(30));
+    });
+}
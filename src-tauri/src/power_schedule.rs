@@ -0,0 +1,261 @@
+use chrono::{Local, TimeZone};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use tauri_plugin_notification::NotificationExt;
+
+// ===== Scheduled Power Actions =====
+//
+// Shutdown, reboot, suspend or hibernate planned for a time of day or after a
+// countdown. Schedules are stored in the config dir so they survive restarts
+// of the app, a warning notification goes out a few minutes before each one,
+// and due actions run through `system_action`. Every change is emitted as
+// `power://schedule-changed` with the full list; an action that was missed or
+// failed is reported with a notification and `power://schedule-failed`.
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(5);
+const WARNING_MS: i64 = 5 * 60_000;
+// A schedule missed by more than this (the app was closed or the machine
+// asleep) is dropped instead of running the moment the app comes back.
+const MISSED_GRACE_MS: i64 = 2 * 60_000;
+
+/// Values accepted for `action`.
+const SCHEDULE_ACTIONS: &[&str] = &["shutdown", "reboot", "suspend", "hibernate"];
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ScheduledAction {
+    pub id: u64,
+    /// "shutdown", "reboot", "suspend" or "hibernate"
+    pub action: String,
+    /// Unix time in milliseconds
+    pub at: i64,
+    pub created_at: i64,
+    /// Whether the warning notification has been shown
+    pub warned: bool,
+}
+
+#[derive(Serialize, Clone)]
+pub struct ScheduleFailure {
+    pub scheduled: ScheduledAction,
+    /// "missed" (the app was closed or the machine asleep at the time) or
+    /// "failed"
+    pub reason: String,
+    /// What `system_action` returned, for "failed"
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+struct ScheduleStore {
+    next_id: u64,
+    actions: Vec<ScheduledAction>,
+}
+
+static SCHEDULE: OnceLock<Mutex<ScheduleStore>> = OnceLock::new();
+
+fn schedule_path() -> PathBuf {
+    crate::get_config_dir().join("power-schedule.json")
+}
+
+fn store() -> &'static Mutex<ScheduleStore> {
+    SCHEDULE.get_or_init(|| {
+        let store = match fs::read_to_string(schedule_path()) {
+            Ok(json) => serde_json::from_str(&json).unwrap_or_else(|e| {
+                log::error!("Ignoring unreadable power schedule: {e}");
+                ScheduleStore::default()
+            }),
+            Err(_) => ScheduleStore::default(),
+        };
+        Mutex::new(store)
+    })
+}
+
+/// Writes to a temp file first, so a crash mid-write never truncates the
+/// schedule.
+fn save(store: &ScheduleStore) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(store).map_err(|e| e.to_string())?;
+    fs::create_dir_all(crate::get_config_dir()).map_err(|e| e.to_string())?;
+    let path = schedule_path();
+    let temp = path.with_extension("json.tmp");
+    fs::write(&temp, json).map_err(|e| e.to_string())?;
+    fs::rename(&temp, &path).map_err(|e| e.to_string())
+}
+
+fn sorted(store: &ScheduleStore) -> Vec<ScheduledAction> {
+    let mut actions = store.actions.clone();
+    actions.sort_by_key(|a| a.at);
+    actions
+}
+
+fn emit_changed(app: &AppHandle, store: &ScheduleStore) {
+    let _ = app.emit("power://schedule-changed", sorted(store));
+}
+
+fn action_verb(action: &str) -> &'static str {
+    match action {
+        "shutdown" => "shut down",
+        "reboot" => "restart",
+        "hibernate" => "hibernate",
+        _ => "suspend",
+    }
+}
+
+fn format_time(at: i64) -> String {
+    Local
+        .timestamp_millis_opt(at)
+        .single()
+        .map(|t| t.format("%H:%M").to_string())
+        .unwrap_or_default()
+}
+
+#[tauri::command]
+pub fn list_scheduled_actions() -> Result<Vec<ScheduledAction>, String> {
+    let store = store().lock().map_err(|e| e.to_string())?;
+    Ok(sorted(&store))
+}
+
+/// Plans `action` for `at` (Unix time in milliseconds) or in `minutes`.
+#[tauri::command]
+pub fn schedule_power_action(
+    app: AppHandle,
+    action: &str,
+    at: Option<i64>,
+    minutes: Option<u32>,
+) -> Result<ScheduledAction, String> {
+    if !SCHEDULE_ACTIONS.contains(&action) {
+        return Err(format!("Cannot schedule action: {action}"));
+    }
+    let now = Local::now().timestamp_millis();
+    let at = match (at, minutes) {
+        (Some(at), None) => at,
+        (None, Some(minutes)) => now + i64::from(minutes) * 60_000,
+        _ => return Err("Give either a time or a countdown".to_string()),
+    };
+    if at <= now {
+        return Err("The scheduled time has already passed".to_string());
+    }
+
+    let mut store = store().lock().map_err(|e| e.to_string())?;
+    store.next_id += 1;
+    let scheduled = ScheduledAction {
+        id: store.next_id,
+        action: action.to_string(),
+        at,
+        created_at: now,
+        // Planned within the warning window: the user knows already.
+        warned: at - now <= WARNING_MS,
+    };
+    store.actions.push(scheduled.clone());
+    save(&store)?;
+    emit_changed(&app, &store);
+    Ok(scheduled)
+}
+
+#[tauri::command]
+pub fn cancel_scheduled_action(app: AppHandle, id: u64) -> Result<(), String> {
+    let mut store = store().lock().map_err(|e| e.to_string())?;
+    let before = store.actions.len();
+    store.actions.retain(|a| a.id != id);
+    if store.actions.len() == before {
+        return Err(format!("No scheduled action with id {id}"));
+    }
+    save(&store)?;
+    emit_changed(&app, &store);
+    Ok(())
+}
+
+fn warn(app: &AppHandle, scheduled: &ScheduledAction, now: i64) {
+    let minutes = ((scheduled.at - now) as f64 / 60_000.0).ceil() as i64;
+    let title = format!(
+        "The computer will {} in {minutes} min",
+        action_verb(&scheduled.action)
+    );
+    let body = format!(
+        "Scheduled for {}. Save your work or cancel it from the power menu.",
+        format_time(scheduled.at)
+    );
+    if let Err(e) = app.notification().builder().title(title).body(body).show() {
+        log::warn!("Failed to show schedule notification: {e}");
+    }
+    let _ = app.emit("power://schedule-warning", scheduled.clone());
+}
+
+fn report_failure(app: &AppHandle, scheduled: ScheduledAction, error: Option<String>) {
+    let title = format!(
+        "The computer did not {} at {}",
+        action_verb(&scheduled.action),
+        format_time(scheduled.at)
+    );
+    let body = match &error {
+        Some(e) => format!("The scheduled {} failed: {}", scheduled.action, e.trim()),
+        None => "The app was not running or the computer was asleep at that time.".to_string(),
+    };
+    if let Err(e) = app.notification().builder().title(title).body(body).show() {
+        log::warn!("Failed to show schedule notification: {e}");
+    }
+    let failure = ScheduleFailure {
+        scheduled,
+        reason: if error.is_some() { "failed" } else { "missed" }.to_string(),
+        error,
+    };
+    let _ = app.emit("power://schedule-failed", failure);
+}
+
+fn check(app: &AppHandle) {
+    let now = Local::now().timestamp_millis();
+    let (due, warnings) = {
+        let Ok(mut store) = store().lock() else {
+            return;
+        };
+        let (due, pending): (Vec<_>, Vec<_>) = store.actions.drain(..).partition(|a| a.at <= now);
+        store.actions = pending;
+
+        let mut warnings = Vec::new();
+        for scheduled in store.actions.iter_mut() {
+            if !scheduled.warned && scheduled.at - now <= WARNING_MS {
+                scheduled.warned = true;
+                warnings.push(scheduled.clone());
+            }
+        }
+        if due.is_empty() && warnings.is_empty() {
+            return;
+        }
+        // Save before acting, so a shutdown never comes back after the reboot.
+        if let Err(e) = save(&store) {
+            log::error!("Failed to save power schedule: {e}");
+        }
+        emit_changed(app, &store);
+        (due, warnings)
+    };
+
+    for scheduled in &warnings {
+        warn(app, scheduled, now);
+    }
+    for scheduled in due {
+        if now - scheduled.at > MISSED_GRACE_MS {
+            log::warn!(
+                "Skipping {} missed at {}",
+                scheduled.action,
+                format_time(scheduled.at)
+            );
+            report_failure(app, scheduled, None);
+            continue;
+        }
+        if let Err(e) = crate::system_action(&scheduled.action) {
+            log::error!("Scheduled {} failed: {e}", scheduled.action);
+            report_failure(app, scheduled, Some(e));
+        }
+    }
+}
+
+/// Starts the watcher that warns about and runs scheduled actions.
+pub fn start(app: &AppHandle) {
+    let app = app.clone();
+    std::thread::spawn(move || loop {
+        check(&app);
+        std::thread::sleep(CHECK_INTERVAL);
+    });
+}
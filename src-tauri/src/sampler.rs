@@ -0,0 +1,236 @@
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+// ===== System Event Sampler =====
+//
+// Instead of every window polling `get_*_info` on its own timer, the backend
+// samples each source on the tokio runtime and pushes `system://<topic>`
+// events to the windows that subscribed to that topic. Events are only sent
+// when the sampled value differs from the previous one.
+
+struct TopicSpec {
+    name: &'static str,
+    interval: Duration,
+    sample: fn() -> Value,
+    /// Fields that change on every sample (e.g. uptime) and are ignored when
+    /// deciding whether the value changed
+    volatile: &'static [&'static str],
+}
+
+fn to_value<T: Serialize>(v: T) -> Value {
+    serde_json::to_value(v).unwrap_or(Value::Null)
+}
+
+const TOPICS: &[TopicSpec] = &[
+    TopicSpec {
+        name: "cpu",
+        interval: Duration::from_secs(2),
+        sample: || to_value(crate::get_system_info()),
+        volatile: &["uptime"],
+    },
+    TopicSpec {
+        name: "network",
+        interval: Duration::from_secs(3),
+        sample: || to_value(crate::network::get_network_info()),
+        volatile: &[],
+    },
+    TopicSpec {
+        name: "network-traffic",
+        interval: Duration::from_secs(2),
+        sample: || to_value(crate::traffic::current_rates()),
+        volatile: &[],
+    },
+    TopicSpec {
+        name: "audio",
+        interval: Duration::from_secs(1),
+        sample: || to_value(crate::get_audio_info()),
+        volatile: &[],
+    },
+    TopicSpec {
+        name: "audio-devices",
+        interval: Duration::from_secs(3),
+        sample: || to_value(crate::audio::list_audio_devices().ok()),
+        volatile: &[],
+    },
+    TopicSpec {
+        name: "audio-streams",
+        interval: Duration::from_secs(2),
+        sample: || to_value(crate::audio::list_audio_streams().ok()),
+        volatile: &[],
+    },
+    TopicSpec {
+        name: "battery",
+        interval: Duration::from_secs(10),
+        sample: || to_value(crate::power::get_battery_info()),
+        volatile: &[],
+    },
+    TopicSpec {
+        name: "inhibit",
+        interval: Duration::from_secs(5),
+        sample: || to_value(crate::inhibit::get_inhibit_status()),
+        volatile: &[],
+    },
+];
+
+#[derive(Default)]
+struct SamplerState {
+    // topic -> window label -> number of subscriptions held by that window
+    subscribers: HashMap<String, HashMap<String, usize>>,
+    // topic -> last value pushed to subscribers
+    last: HashMap<String, Value>,
+}
+
+static SAMPLER: OnceLock<Mutex<SamplerState>> = OnceLock::new();
+
+fn state() -> &'static Mutex<SamplerState> {
+    SAMPLER.get_or_init(|| Mutex::new(SamplerState::default()))
+}
+
+pub fn event_name(topic: &str) -> String {
+    format!("system://{topic}")
+}
+
+pub(crate) fn has_subscribers(topic: &str) -> bool {
+    state()
+        .lock()
+        .map(|s| {
+            s.subscribers
+                .get(topic)
+                .map(|w| !w.is_empty())
+                .unwrap_or(false)
+        })
+        .unwrap_or(false)
+}
+
+/// Compares two samples of `topic`, leaving out its volatile fields.
+fn same_sample(topic: &str, a: &Value, b: &Value) -> bool {
+    let volatile = TOPICS
+        .iter()
+        .find(|t| t.name == topic)
+        .map(|t| t.volatile)
+        .unwrap_or_default();
+    match (a, b) {
+        (Value::Object(a), Value::Object(b)) if !volatile.is_empty() => {
+            let stable = |(key, _): &(&String, &Value)| !volatile.contains(&key.as_str());
+            a.iter().filter(stable).eq(b.iter().filter(stable))
+        }
+        _ => a == b,
+    }
+}
+
+/// Pushes `value` to every window subscribed to `topic`, unless it equals the
+/// last value that was pushed for that topic.
+pub fn publish(app: &AppHandle, topic: &str, value: Value) {
+    let targets: Vec<String> = {
+        let Ok(mut s) = state().lock() else {
+            return;
+        };
+        if s.last
+            .get(topic)
+            .is_some_and(|last| same_sample(topic, last, &value))
+        {
+            return;
+        }
+        s.last.insert(topic.to_string(), value.clone());
+        s.subscribers
+            .get(topic)
+            .map(|w| w.keys().cloned().collect())
+            .unwrap_or_default()
+    };
+
+    let event = event_name(topic);
+    for label in targets {
+        let _ = app.emit_to(label.as_str(), &event, value.clone());
+    }
+}
+
+/// Starts one sampling loop per topic. Loops idle while nobody is subscribed,
+/// so no `nmcli`/`pactl` processes are spawned for unused topics.
+pub fn start(app: &AppHandle) {
+    for spec in TOPICS {
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            let mut ticker = tokio::time::interval(spec.interval);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+            loop {
+                ticker.tick().await;
+                if !has_subscribers(spec.name) {
+                    continue;
+                }
+                let sample = spec.sample;
+                if let Ok(value) = tauri::async_runtime::spawn_blocking(sample).await {
+                    publish(&app, spec.name, value);
+                }
+            }
+        });
+    }
+}
+
+/// Drops all subscriptions held by a window (called when it is destroyed).
+pub fn forget_window(label: &str) {
+    if let Ok(mut s) = state().lock() {
+        for windows in s.subscribers.values_mut() {
+            windows.remove(label);
+        }
+    }
+}
+
+#[tauri::command]
+pub fn list_event_topics() -> Vec<String> {
+    TOPICS.iter().map(|t| t.name.to_string()).collect()
+}
+
+#[tauri::command]
+pub fn subscribe_events(window: tauri::Window, topics: Vec<String>) -> Result<(), String> {
+    let label = window.label().to_string();
+    let mut initial = Vec::new();
+    {
+        let mut s = state().lock().map_err(|e| e.to_string())?;
+        for topic in &topics {
+            if !TOPICS.iter().any(|t| t.name == topic) {
+                return Err(format!("Unknown topic: {topic}"));
+            }
+        }
+        for topic in topics {
+            if let Some(value) = s.last.get(&topic) {
+                initial.push((event_name(&topic), value.clone()));
+            }
+            *s.subscribers
+                .entry(topic)
+                .or_default()
+                .entry(label.clone())
+                .or_default() += 1;
+        }
+    }
+
+    // Send the latest known values right away so a freshly opened window does
+    // not have to wait for the next change.
+    for (event, value) in initial {
+        let _ = window.emit_to(label.as_str(), &event, value);
+    }
+    Ok(())
+}
+
+/// Releases one subscription per topic. A window keeps receiving a topic
+/// until every `subscribe_events` call for it has been matched.
+#[tauri::command]
+pub fn unsubscribe_events(window: tauri::Window, topics: Vec<String>) -> Result<(), String> {
+    let label = window.label();
+    let mut s = state().lock().map_err(|e| e.to_string())?;
+    for topic in topics {
+        let Some(windows) = s.subscribers.get_mut(&topic) else {
+            continue;
+        };
+        if let Some(count) = windows.get_mut(label) {
+            *count -= 1;
+            if *count == 0 {
+                windows.remove(label);
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,397 @@
+use chrono::{Duration as ChronoDuration, Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use sysinfo::Networks;
+
+// ===== Network Traffic =====
+//
+// A background thread reads the per-interface byte counters every
+// SAMPLE_INTERVAL. It keeps the last hour of RX/TX rates in memory for graphs
+// and adds the transferred bytes to per-day totals in data-usage.json, so data
+// usage survives restarts.
+
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(2);
+const HISTORY_LEN: usize = 1800; // one hour of samples
+const SAVE_INTERVAL: Duration = Duration::from_secs(60);
+const USAGE_RETENTION_DAYS: i64 = 400;
+
+#[derive(Serialize, Clone, Copy, PartialEq)]
+pub struct TrafficSample {
+    /// Unix time in milliseconds
+    pub timestamp: i64,
+    /// Bytes per second
+    pub rx_rate: u64,
+    pub tx_rate: u64,
+}
+
+#[derive(Serialize, Clone, PartialEq)]
+pub struct InterfaceTraffic {
+    pub interface: String,
+    /// Oldest first
+    pub samples: Vec<TrafficSample>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+pub struct UsageTotals {
+    pub rx_bytes: u64,
+    pub tx_bytes: u64,
+}
+
+impl UsageTotals {
+    fn add(&mut self, other: UsageTotals) {
+        self.rx_bytes += other.rx_bytes;
+        self.tx_bytes += other.tx_bytes;
+    }
+}
+
+#[derive(Serialize, Clone)]
+pub struct PeriodUsage {
+    /// "YYYY-MM-DD" for days, "YYYY-MM" for months
+    pub period: String,
+    pub rx_bytes: u64,
+    pub tx_bytes: u64,
+}
+
+#[derive(Serialize, Clone)]
+pub struct DataUsage {
+    pub today: UsageTotals,
+    pub this_month: UsageTotals,
+    /// Oldest first
+    pub daily: Vec<PeriodUsage>,
+    /// Oldest first
+    pub monthly: Vec<PeriodUsage>,
+    /// Interfaces that have recorded usage
+    pub interfaces: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+struct UsageStore {
+    // "YYYY-MM-DD" -> interface -> bytes transferred that day
+    days: BTreeMap<String, HashMap<String, UsageTotals>>,
+}
+
+struct TrafficState {
+    history: HashMap<String, VecDeque<TrafficSample>>,
+    usage: UsageStore,
+    dirty: bool,
+}
+
+static TRAFFIC: OnceLock<Mutex<TrafficState>> = OnceLock::new();
+
+fn usage_path() -> PathBuf {
+    crate::get_config_dir().join("data-usage.json")
+}
+
+fn state() -> &'static Mutex<TrafficState> {
+    TRAFFIC.get_or_init(|| {
+        let usage = fs::read_to_string(usage_path())
+            .ok()
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default();
+        Mutex::new(TrafficState {
+            history: HashMap::new(),
+            usage,
+            dirty: false,
+        })
+    })
+}
+
+fn write_usage(json: &str) -> Result<(), String> {
+    fs::create_dir_all(crate::get_config_dir()).map_err(|e| e.to_string())?;
+    let path = usage_path();
+    let temp = path.with_extension("json.tmp");
+    fs::write(&temp, json).map_err(|e| e.to_string())?;
+    fs::rename(&temp, &path).map_err(|e| e.to_string())
+}
+
+fn save_usage() -> Result<(), String> {
+    let json = {
+        let mut s = state().lock().map_err(|e| e.to_string())?;
+        if !s.dirty {
+            return Ok(());
+        }
+        s.dirty = false;
+        serde_json::to_string(&s.usage).map_err(|e| e.to_string())?
+    };
+    write_usage(&json).inspect_err(|_| {
+        // Try again on the next tick rather than after the next traffic.
+        if let Ok(mut s) = state().lock() {
+            s.dirty = true;
+        }
+    })
+}
+
+/// Saves usage recorded since the last periodic save; called on exit.
+pub fn flush() {
+    if let Err(e) = save_usage() {
+        log::warn!("Failed to save data usage: {e}");
+    }
+}
+
+fn record(networks: &Networks, elapsed: f64) {
+    let now = Local::now();
+    let today = now.format("%Y-%m-%d").to_string();
+    let Ok(mut s) = state().lock() else {
+        return;
+    };
+
+    for (name, data) in networks.iter() {
+        if name == "lo" {
+            continue;
+        }
+        let delta = UsageTotals {
+            rx_bytes: data.received(),
+            tx_bytes: data.transmitted(),
+        };
+        let history = s.history.entry(name.clone()).or_default();
+        if history.len() >= HISTORY_LEN {
+            history.pop_front();
+        }
+        history.push_back(TrafficSample {
+            timestamp: now.timestamp_millis(),
+            rx_rate: (delta.rx_bytes as f64 / elapsed).round() as u64,
+            tx_rate: (delta.tx_bytes as f64 / elapsed).round() as u64,
+        });
+
+        if delta.rx_bytes > 0 || delta.tx_bytes > 0 {
+            s.usage
+                .days
+                .entry(today.clone())
+                .or_default()
+                .entry(name.clone())
+                .or_default()
+                .add(delta);
+            s.dirty = true;
+        }
+    }
+    // Interfaces that disappeared (unplugged tethering, stopped VPN) keep
+    // their history until it has aged out.
+    let cutoff = now.timestamp_millis() - (HISTORY_LEN as i64 * SAMPLE_INTERVAL.as_millis() as i64);
+    s.history
+        .retain(|_, samples| samples.back().is_some_and(|last| last.timestamp >= cutoff));
+
+    let oldest = (now.date_naive() - ChronoDuration::days(USAGE_RETENTION_DAYS))
+        .format("%Y-%m-%d")
+        .to_string();
+    s.usage.days = s.usage.days.split_off(&oldest);
+}
+
+/// Starts the traffic sampling thread.
+pub fn start() {
+    std::thread::spawn(|| {
+        let mut networks = Networks::new_with_refreshed_list();
+        let mut last_tick = Instant::now();
+        let mut last_save = Instant::now();
+        loop {
+            std::thread::sleep(SAMPLE_INTERVAL);
+            // refresh_list also picks up interfaces that appeared since the last tick.
+            networks.refresh_list();
+            let elapsed = last_tick.elapsed().as_secs_f64().max(0.001);
+            last_tick = Instant::now();
+            record(&networks, elapsed);
+
+            if last_save.elapsed() >= SAVE_INTERVAL {
+                flush();
+                last_save = Instant::now();
+            }
+        }
+    });
+}
+
+/// Latest RX/TX rate of every interface (used by the sampler).
+pub(crate) fn current_rates() -> Vec<InterfaceTraffic> {
+    let Ok(s) = state().lock() else {
+        return Vec::new();
+    };
+    let mut rates: Vec<InterfaceTraffic> = s
+        .history
+        .iter()
+        .filter_map(|(name, samples)| {
+            Some(InterfaceTraffic {
+                interface: name.clone(),
+                samples: vec![*samples.back()?],
+            })
+        })
+        .collect();
+    rates.sort_by(|a, b| a.interface.cmp(&b.interface));
+    rates
+}
+
+#[tauri::command]
+pub fn get_network_traffic_history(
+    minutes: u32,
+    interface: Option<String>,
+) -> Result<Vec<InterfaceTraffic>, String> {
+    let since = Local::now().timestamp_millis() - i64::from(minutes) * 60_000;
+    let s = state().lock().map_err(|e| e.to_string())?;
+    let mut history: Vec<InterfaceTraffic> = s
+        .history
+        .iter()
+        .filter(|(name, _)| interface.as_deref().is_none_or(|i| i == name.as_str()))
+        .map(|(name, samples)| InterfaceTraffic {
+            interface: name.clone(),
+            samples: samples
+                .iter()
+                .filter(|sample| sample.timestamp >= since)
+                .copied()
+                .collect(),
+        })
+        .collect();
+    history.sort_by(|a, b| a.interface.cmp(&b.interface));
+    Ok(history)
+}
+
+fn period_usage(period: String, usage: UsageTotals) -> PeriodUsage {
+    PeriodUsage {
+        period,
+        rx_bytes: usage.rx_bytes,
+        tx_bytes: usage.tx_bytes,
+    }
+}
+
+/// Daily and monthly totals of `interface` (every interface when `None`),
+/// with the daily list limited to the last `days` days up to `today`.
+fn summarize(
+    usage: &UsageStore,
+    interface: Option<&str>,
+    days: Option<u32>,
+    today: NaiveDate,
+) -> DataUsage {
+    let this_month = today.format("%Y-%m").to_string();
+    let first_day = days.map(|d| {
+        (today - ChronoDuration::days(i64::from(d.max(1)) - 1))
+            .format("%Y-%m-%d")
+            .to_string()
+    });
+    let today = today.format("%Y-%m-%d").to_string();
+
+    let mut interfaces: Vec<String> = Vec::new();
+    let mut daily = Vec::new();
+    let mut monthly: BTreeMap<String, UsageTotals> = BTreeMap::new();
+    for (day, per_interface) in &usage.days {
+        let mut total = UsageTotals::default();
+        for (name, usage) in per_interface {
+            if !interfaces.contains(name) {
+                interfaces.push(name.clone());
+            }
+            if interface.is_none_or(|i| i == name) {
+                total.add(*usage);
+            }
+        }
+        if let Some(month) = day.get(..7) {
+            monthly.entry(month.to_string()).or_default().add(total);
+        }
+        if first_day.as_ref().is_none_or(|first| day >= first) {
+            daily.push(period_usage(day.clone(), total));
+        }
+    }
+    interfaces.sort();
+
+    let today_usage = daily
+        .iter()
+        .find(|d| d.period == today)
+        .map(|d| UsageTotals {
+            rx_bytes: d.rx_bytes,
+            tx_bytes: d.tx_bytes,
+        })
+        .unwrap_or_default();
+    DataUsage {
+        today: today_usage,
+        this_month: monthly.get(&this_month).copied().unwrap_or_default(),
+        daily,
+        monthly: monthly
+            .into_iter()
+            .map(|(period, usage)| period_usage(period, usage))
+            .collect(),
+        interfaces,
+    }
+}
+
+/// Data usage per day and per month, for one interface or all of them.
+#[tauri::command]
+pub fn get_data_usage(interface: Option<String>, days: Option<u32>) -> Result<DataUsage, String> {
+    let s = state().lock().map_err(|e| e.to_string())?;
+    Ok(summarize(
+        &s.usage,
+        interface.as_deref(),
+        days,
+        Local::now().date_naive(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn totals(rx_bytes: u64, tx_bytes: u64) -> UsageTotals {
+        UsageTotals { rx_bytes, tx_bytes }
+    }
+
+    fn store(days: &[(&str, &str, UsageTotals)]) -> UsageStore {
+        let mut usage = UsageStore::default();
+        for (day, interface, totals) in days {
+            usage
+                .days
+                .entry(day.to_string())
+                .or_default()
+                .insert(interface.to_string(), *totals);
+        }
+        usage
+    }
+
+    fn date(day: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(day, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn months_add_up_their_days() {
+        let usage = store(&[
+            ("2026-09-30", "wlan0", totals(100, 10)),
+            ("2026-10-01", "wlan0", totals(200, 20)),
+            ("2026-10-01", "enp3s0", totals(5, 5)),
+            ("2026-10-16", "wlan0", totals(300, 30)),
+        ]);
+
+        let summary = summarize(&usage, None, None, date("2026-10-16"));
+        let monthly: Vec<(&str, u64, u64)> = summary
+            .monthly
+            .iter()
+            .map(|m| (m.period.as_str(), m.rx_bytes, m.tx_bytes))
+            .collect();
+        assert_eq!(monthly, vec![("2026-09", 100, 10), ("2026-10", 505, 55)]);
+        assert!(summary.this_month == totals(505, 55));
+        assert!(summary.today == totals(300, 30));
+        assert_eq!(summary.interfaces, vec!["enp3s0", "wlan0"]);
+    }
+
+    #[test]
+    fn daily_list_honours_interface_and_range() {
+        let usage = store(&[
+            ("2026-10-13", "wlan0", totals(1, 1)),
+            ("2026-10-14", "wlan0", totals(2, 2)),
+            ("2026-10-15", "enp3s0", totals(4, 4)),
+            ("2026-10-16", "wlan0", totals(8, 8)),
+        ]);
+
+        let summary = summarize(&usage, Some("wlan0"), Some(3), date("2026-10-16"));
+        let daily: Vec<(&str, u64)> = summary
+            .daily
+            .iter()
+            .map(|d| (d.period.as_str(), d.rx_bytes))
+            .collect();
+        // The 15th only saw wired traffic, so it counts as zero for Wi-Fi.
+        assert_eq!(
+            daily,
+            vec![("2026-10-14", 2), ("2026-10-15", 0), ("2026-10-16", 8)]
+        );
+        // Months cover every stored day, not just the requested range.
+        assert!(summary.this_month == totals(11, 11));
+        // Every interface is listed so the UI can offer the filter.
+        assert_eq!(summary.interfaces, vec!["enp3s0", "wlan0"]);
+    }
+}
@@ -0,0 +1,103 @@
+use std::collections::HashMap;
+use zbus::blocking::Connection;
+use zbus::zvariant::{OwnedObjectPath, OwnedValue};
+
+use crate::bus::Properties;
+
+// ===== UPower D-Bus Client =====
+//
+// Typed proxies for the UPower daemon, which reports laptop batteries, UPS
+// units, AC adapters and the batteries of wireless peripherals.
+
+pub const DEVICE_TYPE_LINE_POWER: u32 = 1;
+pub const DEVICE_TYPE_BATTERY: u32 = 2;
+pub const DEVICE_TYPE_UPS: u32 = 3;
+
+pub const DEVICE_STATE_CHARGING: u32 = 1;
+
+#[zbus::proxy(
+    interface = "org.freedesktop.UPower",
+    default_service = "org.freedesktop.UPower",
+    default_path = "/org/freedesktop/UPower"
+)]
+pub trait UPower {
+    fn enumerate_devices(&self) -> zbus::Result<Vec<OwnedObjectPath>>;
+
+    #[zbus(property)]
+    fn on_battery(&self) -> zbus::Result<bool>;
+}
+
+#[zbus::proxy(
+    interface = "org.freedesktop.DBus.Properties",
+    default_service = "org.freedesktop.UPower"
+)]
+pub trait UPowerProperties {
+    fn get_all(&self, interface_name: &str) -> zbus::Result<HashMap<String, OwnedValue>>;
+}
+
+#[zbus::proxy(
+    interface = "org.freedesktop.UPower.Device",
+    default_service = "org.freedesktop.UPower"
+)]
+pub trait Device {
+    #[zbus(property)]
+    fn native_path(&self) -> zbus::Result<String>;
+
+    #[zbus(property)]
+    fn serial(&self) -> zbus::Result<String>;
+
+    #[zbus(property)]
+    fn percentage(&self) -> zbus::Result<f64>;
+
+    #[zbus(property)]
+    fn is_present(&self) -> zbus::Result<bool>;
+}
+
+pub fn upower(conn: &Connection) -> Result<UPowerProxyBlocking<'static>, String> {
+    UPowerProxyBlocking::new(conn).map_err(|e| e.to_string())
+}
+
+pub fn device_paths(conn: &Connection) -> Result<Vec<OwnedObjectPath>, String> {
+    upower(conn)?
+        .enumerate_devices()
+        .map_err(|e| format!("Cannot reach UPower: {e}"))
+}
+
+/// Every power source UPower knows about.
+pub fn devices(conn: &Connection) -> Result<Vec<DeviceProxyBlocking<'static>>, String> {
+    device_paths(conn)?
+        .into_iter()
+        .map(|path| DeviceProxyBlocking::new(conn, path).map_err(|e| e.to_string()))
+        .collect()
+}
+
+/// All properties of a device in one call.
+pub fn device_properties(conn: &Connection, path: &OwnedObjectPath) -> Result<Properties, String> {
+    UPowerPropertiesProxyBlocking::new(conn, path)
+        .and_then(|p| p.get_all("org.freedesktop.UPower.Device"))
+        .map_err(|e| e.to_string())
+}
+
+pub fn state_name(state: u32) -> &'static str {
+    match state {
+        1 => "charging",
+        2 => "discharging",
+        3 => "empty",
+        4 => "full",
+        5 => "pending-charge",
+        6 => "pending-discharge",
+        _ => "unknown",
+    }
+}
+
+pub fn technology_name(technology: u32) -> &'static str {
+    match technology {
+        1 => "lithium-ion",
+        2 => "lithium-polymer",
+        3 => "lithium-iron-phosphate",
+        4 => "lead-acid",
+        5 => "nickel-cadmium",
+        6 => "nickel-metal-hydride",
+        _ => "unknown",
+    }
+}
@@ -1,6 +1,7 @@
 import { useState, useEffect, useCallback, useRef } from 'react';
 import { invoke } from '@tauri-apps/api/core';
 import { convertFileSrc } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
 import { 
   Folder, 
   Globe, 
@@ -31,7 +32,12 @@ import {
   LayoutGrid,
   ShieldCheck,
   Activity,
-  FolderOpen
+  FolderOpen,
+  Zap,
+  Leaf,
+  Gauge,
+  Sunset,
+  Coffee
 } from 'lucide-react';
 
 import { SettingsWindow } from './components/SettingsWindow';
@@ -45,7 +51,7 @@ import { TrainTimerWidget } from './components/widgets/TrainTimerWidget';
 import { TodoWidget } from './components/widgets/TodoWidget';
 import { MusicControlWidget } from './components/widgets/MusicControlWidget';
 
-import type { DesktopSettings, FileEntry, SystemInfo, BatteryInfo, NetworkInfo, AudioInfo } from './types';
+import type { DesktopSettings, FileEntry, SystemInfo, BatteryInfo, PowerStatus, PowerProfiles, NightLightStatus, InhibitStatus, ScheduledAction, ScheduledPowerAction, ScheduleFailure, NetworkInfo, AudioInfo, ConnectivityInfo } from './types';
 
 type UserProfile = {
   username: string;
@@ -80,6 +86,14 @@ const App = () => {
   // System data - REAL from system
   const [systemInfo, setSystemInfo] = useState<SystemInfo | null>(null);
   const [batteryInfo, setBatteryInfo] = useState<BatteryInfo | null>(null);
+  const [powerProfiles, setPowerProfiles] = useState<PowerProfiles | null>(null);
+  const [scheduledActions, setScheduledActions] = useState<ScheduledAction[]>([]);
+  const [scheduleAction, setScheduleAction] = useState<ScheduledPowerAction>('shutdown');
+  const [scheduleTime, setScheduleTime] = useState('');
+  const [scheduleFailure, setScheduleFailure] = useState<ScheduleFailure | null>(null);
+  const [inhibitStatus, setInhibitStatus] = useState<InhibitStatus | null>(null);
+  const [nightLight, setNightLight] = useState<NightLightStatus | null>(null);
+  const nightLightCommitTimer = useRef<number | null>(null);
   const [networkInfo, setNetworkInfo] = useState<NetworkInfo | null>(null);
   const [audioInfo, setAudioInfo] = useState<AudioInfo | null>(null);
   const [uiVolume, setUiVolume] = useState<number>(50);
@@ -166,48 +180,74 @@ const App = () => {
     }
   }, [settings?.wallpaper]);
 
-  // ==================== SYSTEM DATA POLLING ====================
+  // ==================== SYSTEM EVENTS ====================
+  // The backend samples system state and pushes `system://<topic>` events
+  // only when a value changes, so no polling timers are needed here.
   useEffect(() => {
     if (scene !== 'desktop') return;
 
-    const fetchAll = async () => {
-      try {
-        const [sys, bat, net] = await Promise.all([
-          invoke<SystemInfo>('get_system_info'),
-          invoke<BatteryInfo | null>('get_battery_info'),
-          invoke<NetworkInfo>('get_network_info'),
-        ]);
-        setSystemInfo(sys);
-        setBatteryInfo(bat);
-        setNetworkInfo(net);
-      } catch (e) {
-        console.error('System fetch error:', e);
-      }
+    const topics = ['cpu', 'battery', 'network', 'audio', 'inhibit'];
+    const unlisteners = [
+      listen<SystemInfo>('system://cpu', (e) => setSystemInfo(e.payload)),
+      listen<PowerStatus>('system://battery', (e) => setBatteryInfo(e.payload.aggregate)),
+      listen<NetworkInfo>('system://network', (e) => setNetworkInfo(e.payload)),
+      listen<AudioInfo>('system://audio', (e) => setAudioInfo(e.payload)),
+      listen<InhibitStatus>('system://inhibit', (e) => setInhibitStatus(e.payload)),
+    ];
+
+    // Resolves to whether the subscription was taken, so the cleanup only
+    // releases it after it exists.
+    const subscribed = Promise.all(unlisteners)
+      .then(() => invoke('subscribe_events', { topics }))
+      .then(() => true)
+      .catch((e) => {
+        console.error('Subscribe to system events failed:', e);
+        return false;
+      });
+
+    return () => {
+      subscribed.then((ok) => {
+        if (ok) invoke('unsubscribe_events', { topics }).catch(console.error);
+      });
+      unlisteners.forEach((p) => p.then((unlisten) => unlisten()));
     };
-
-    fetchAll();
-    const interval = setInterval(fetchAll, 5000);  // Increased from 2000ms to 5000ms for better responsiveness
-    return () => clearInterval(interval);
   }, [scene]);
 
-  // ==================== AUDIO POLLING (MPRIS) ====================
+  // Power profiles are not sampled: power-profiles-daemon announces changes.
   useEffect(() => {
     if (scene !== 'desktop') return;
 
-    const fetchAudio = async () => {
-      try {
-        const aud = await invoke<AudioInfo>('get_audio_info');
-        setAudioInfo(aud);
-      } catch (e) {
-        console.error('Audio fetch error:', e);
-      }
+    invoke<PowerProfiles>('get_power_profiles')
+      .then(setPowerProfiles)
+      .catch(() => setPowerProfiles(null));
+    const unlisten = listen<PowerProfiles>('power://profiles-changed', (e) => setPowerProfiles(e.payload));
+    return () => {
+      unlisten.then((u) => u());
     };
+  }, [scene]);
 
-    fetchAudio();
-    const interval = setInterval(fetchAudio, 1000);
-    return () => clearInterval(interval);
+  useEffect(() => {
+    if (scene !== 'desktop') return;
+
+    invoke<ScheduledAction[]>('list_scheduled_actions')
+      .then(setScheduledActions)
+      .catch(() => setScheduledActions([]));
+    const unlisten = listen<ScheduledAction[]>('power://schedule-changed', (e) => setScheduledActions(e.payload));
+    const unlistenFailed = listen<ScheduleFailure>('power://schedule-failed', (e) => setScheduleFailure(e.payload));
+    return () => {
+      unlisten.then((u) => u());
+      unlistenFailed.then((u) => u());
+    };
   }, [scene]);
 
+  // Night light can switch on and off with its schedule, so refresh whenever the menu opens.
+  useEffect(() => {
+    if (scene !== 'desktop' || !menuOpen) return;
+    invoke<NightLightStatus>('get_night_light')
+      .then(setNightLight)
+      .catch(() => setNightLight(null));
+  }, [scene, menuOpen]);
+
   useEffect(() => {
     if (typeof audioInfo?.volume === 'number') {
       setUiVolume(audioInfo.volume);
@@ -268,12 +308,73 @@ const App = () => {
     setShowPowerMenu(false);
   };
 
+  // Quick switch between "performance" (e.g. while compiling) and "balanced".
+  const togglePerformance = async () => {
+    if (!powerProfiles) return;
+    const profile = powerProfiles.active === 'performance' ? 'balanced' : 'performance';
+    try {
+      await invoke('set_power_profile', { profile });
+    } catch (e) {
+      console.error('Set power profile failed:', e);
+    }
+  };
+
+  const setNightLightState = async (enabled: boolean, temperature?: number) => {
+    try {
+      const status = await invoke<NightLightStatus>('set_night_light', { enabled, temperature });
+      setNightLight(status);
+      setSettings((prev) =>
+        prev ? { ...prev, night_light_enabled: status.enabled, night_light_temperature: status.temperature } : prev
+      );
+    } catch (e) {
+      console.error('Set night light failed:', e);
+    }
+  };
+
+  const schedulePowerAction = async (action: ScheduledPowerAction, when: { minutes: number } | { at: number }) => {
+    try {
+      await invoke('schedule_power_action', { action, ...when });
+      setScheduleFailure(null);
+    } catch (e) {
+      console.error('Schedule power action failed:', e);
+    }
+  };
+
+  // "HH:MM" today, or tomorrow when that time has passed.
+  const scheduleAt = (time: string) => {
+    const [hours, minutes] = time.split(':').map(Number);
+    const at = new Date();
+    at.setHours(hours, minutes, 0, 0);
+    if (at.getTime() <= Date.now()) at.setDate(at.getDate() + 1);
+    return at.getTime();
+  };
+
+  const cancelScheduledAction = async (id: number) => {
+    try {
+      await invoke('cancel_scheduled_action', { id });
+    } catch (e) {
+      console.error('Cancel scheduled action failed:', e);
+    }
+  };
+
+  // Keeps the machine from idling or sleeping until toggled off again.
+  const toggleStayAwake = async () => {
+    try {
+      const status = inhibitStatus?.lock
+        ? await invoke<InhibitStatus>('release_inhibitor')
+        : await invoke<InhibitStatus>('take_inhibitor', { reason: 'Stay awake requested from the desktop' });
+      setInhibitStatus(status);
+    } catch (e) {
+      console.error('Toggle stay awake failed:', e);
+    }
+  };
+
   // ==================== AUDIO CONTROLS ====================
   const setVolume = useCallback(async (vol: number) => {
     try {
       setUiVolume(vol);  // Optimistic update
       await invoke('set_volume', { volume: vol });
-      // Fetch audio info on next poll, don't block UI
+      // The next system://audio event will confirm the new value
     } catch (e) {
       console.error('Set volume failed:', e);
     }
@@ -308,6 +409,15 @@ const App = () => {
     }
   };
 
+  const openCaptivePortal = async () => {
+    try {
+      const info = await invoke<ConnectivityInfo>('get_connectivity');
+      if (info.portal_url) await openFile(info.portal_url);
+    } catch (e) {
+      console.error('Captive portal lookup failed:', e);
+    }
+  };
+
   // ==================== FORMATTERS ====================
   const formatTime = (d: Date) => d.toLocaleTimeString('ja-JP', { hour: '2-digit', minute: '2-digit' });
   const formatDate = (d: Date) => d.toLocaleDateString('ja-JP', { month: 'short', day: 'numeric', weekday: 'short' });
@@ -617,6 +727,34 @@ const App = () => {
           <div className="flex items-center gap-3 text-white/80">
             {networkInfo?.is_connected ? <Wifi size={16} /> : <WifiOff size={16} className="text-red-300" />}
             {batteryInfo && <span className="text-[11px] font-black">{Math.round(batteryInfo.percentage)}%</span>}
+            <button
+              onClick={toggleStayAwake}
+              className={`transition-colors ${inhibitStatus?.staying_awake ? 'text-amber-300 hover:text-amber-200' : 'hover:text-white'}`}
+              title={
+                inhibitStatus?.lock
+                  ? `Staying awake${inhibitStatus.lock.expires_at ? ` until ${new Date(inhibitStatus.lock.expires_at).toLocaleTimeString()}` : ''}`
+                  : inhibitStatus?.media_player
+                    ? `Staying awake while ${inhibitStatus.media_player} is playing`
+                    : 'Stay awake'
+              }
+            >
+              <Coffee size={16} />
+            </button>
+            {powerProfiles?.profiles.some((p) => p.name === 'performance') && (
+              <button
+                onClick={togglePerformance}
+                className="hover:text-white transition-colors"
+                title={`Power profile: ${powerProfiles.active}${powerProfiles.degraded ? ` (limited: ${powerProfiles.degraded})` : ''}`}
+              >
+                {powerProfiles.active === 'performance' ? (
+                  <Zap size={16} className={powerProfiles.degraded ? 'text-yellow-300' : ''} />
+                ) : powerProfiles.active === 'power-saver' ? (
+                  <Leaf size={16} />
+                ) : (
+                  <Gauge size={16} />
+                )}
+              </button>
+            )}
           </div>
         </div>
 
@@ -680,6 +818,50 @@ const App = () => {
                       </button>
                     ))}
                   </div>
+                  {nightLight && nightLight.backend !== 'none' && (
+                    <div className={`mt-4 p-4 rounded-[2rem] flex items-center gap-3 ${panelCardClass}`}>
+                      <button
+                        onClick={() => setNightLightState(!nightLight.enabled)}
+                        className={`p-3 rounded-2xl transition-all shadow-lg ${
+                          nightLight.enabled ? 'bg-orange-500 text-white' : isDarkTheme ? 'bg-white/10 text-slate-300' : 'bg-slate-100 text-slate-500'
+                        }`}
+                        title={
+                          nightLight.schedule === 'manual'
+                            ? 'Night light'
+                            : `Night light (${nightLight.schedule} schedule${nightLight.enabled && !nightLight.active ? ', waiting' : ''})`
+                        }
+                      >
+                        <Sunset size={18} />
+                      </button>
+                      <div className="flex-1 flex flex-col gap-2">
+                        <div className="flex items-center justify-between">
+                          <span className={`text-[10px] font-black uppercase tracking-widest ${isDarkTheme ? 'text-slate-100' : 'text-slate-700'}`}>
+                            Night Light
+                          </span>
+                          <span className={`text-[10px] font-bold ${textSubClass}`}>{nightLight.temperature}K</span>
+                        </div>
+                        <input
+                          type="range"
+                          min="1700"
+                          max="6500"
+                          step="100"
+                          value={nightLight.temperature}
+                          onChange={(e) => {
+                            const next = Number(e.target.value);
+                            setNightLight((prev) => (prev ? { ...prev, temperature: next } : prev));
+                            if (nightLightCommitTimer.current) {
+                              window.clearTimeout(nightLightCommitTimer.current);
+                            }
+                            nightLightCommitTimer.current = window.setTimeout(() => {
+                              setNightLightState(nightLight.enabled, next);
+                            }, 250);
+                          }}
+                          className="w-full h-1 rounded-full appearance-none cursor-pointer"
+                          style={{ background: 'linear-gradient(to right, #f97316, #fde68a, #e0f2fe)' }}
+                        />
+                      </div>
+                    </div>
+                  )}
                   <div className={`mt-8 pt-6 border-t flex items-center justify-between ${isDarkTheme ? 'border-white/10' : 'border-blue-50'}`}>
                     <div className="flex items-center gap-3">
                       {userAvatarSrc && (
@@ -820,6 +1002,82 @@ const App = () => {
                 <span className="text-sm font-bold">{item.label}</span>
               </button>
             ))}
+            <div className="mt-3 pt-3 border-t border-white/40">
+              <div className="text-[10px] font-black tracking-[0.2em] uppercase mb-2 px-1 text-slate-400">Schedule</div>
+              <div className="flex gap-1 px-1 mb-2">
+                {([
+                  { label: 'Shutdown', action: 'shutdown' },
+                  { label: 'Restart', action: 'reboot' },
+                  { label: 'Sleep', action: 'suspend' },
+                ] as { label: string; action: ScheduledPowerAction }[]).map((item) => (
+                  <button
+                    key={item.action}
+                    onClick={() => setScheduleAction(item.action)}
+                    className={`flex-1 py-1 rounded-lg text-[10px] font-bold transition-all ${
+                      scheduleAction === item.action ? 'bg-white/60 text-slate-800' : 'text-slate-500 hover:bg-white/40'
+                    }`}
+                  >
+                    {item.label}
+                  </button>
+                ))}
+              </div>
+              <div className="flex gap-2 px-1">
+                {[30, 60, 120].map((minutes) => (
+                  <button
+                    key={minutes}
+                    onClick={() => schedulePowerAction(scheduleAction, { minutes })}
+                    className="flex-1 py-1.5 rounded-lg text-[11px] font-bold text-slate-600 hover:bg-white/40 transition-all"
+                  >
+                    {minutes < 60 ? `${minutes}m` : `${minutes / 60}h`}
+                  </button>
+                ))}
+              </div>
+              <div className="flex items-center gap-2 mt-2 px-1">
+                <input
+                  type="time"
+                  value={scheduleTime}
+                  onChange={(e) => setScheduleTime(e.target.value)}
+                  className="flex-1 min-w-0 px-2 py-1 rounded-lg bg-white/40 text-[11px] font-bold text-slate-600 outline-none"
+                />
+                <button
+                  onClick={() => schedulePowerAction(scheduleAction, { at: scheduleAt(scheduleTime) })}
+                  disabled={!scheduleTime}
+                  className="px-3 py-1.5 rounded-lg text-[11px] font-bold text-slate-600 hover:bg-white/40 transition-all disabled:opacity-40"
+                >
+                  At time
+                </button>
+              </div>
+              {scheduleFailure && (
+                <div className="flex items-center justify-between mt-2 px-1 text-red-500">
+                  <span className="text-[11px] font-bold">
+                    {scheduleFailure.reason === 'missed' ? 'Missed' : 'Failed'}: {scheduleFailure.scheduled.action} at{' '}
+                    {new Date(scheduleFailure.scheduled.at).toLocaleTimeString([], { hour: '2-digit', minute: '2-digit' })}
+                  </span>
+                  <button
+                    onClick={() => setScheduleFailure(null)}
+                    className="text-red-400 hover:text-red-600 transition-colors"
+                    title={scheduleFailure.error ?? 'Dismiss'}
+                  >
+                    <X size={14} />
+                  </button>
+                </div>
+              )}
+              {scheduledActions.map((scheduled) => (
+                <div key={scheduled.id} className="flex items-center justify-between mt-2 px-1 text-slate-600">
+                  <span className="text-[11px] font-bold capitalize">
+                    {scheduled.action} at{' '}
+                    {new Date(scheduled.at).toLocaleTimeString([], { hour: '2-digit', minute: '2-digit' })}
+                  </span>
+                  <button
+                    onClick={() => cancelScheduledAction(scheduled.id)}
+                    className="text-slate-400 hover:text-red-500 transition-colors"
+                    title="Cancel"
+                  >
+                    <X size={14} />
+                  </button>
+                </div>
+              ))}
+            </div>
           </div>
         </>
       )}
@@ -870,13 +1128,18 @@ const App = () => {
                 <div className="text-lg font-black text-slate-800">{batteryInfo ? Math.round(batteryInfo.percentage) : '--'}%</div>
                 <div className="text-[8px] text-slate-400 uppercase font-bold">BAT</div>
               </div>
-              <div className="text-center p-3 bg-white/30 rounded-2xl">
+              <div
+                className={`text-center p-3 bg-white/30 rounded-2xl ${networkInfo?.connectivity === 'portal' ? 'cursor-pointer hover:bg-white/50' : ''}`}
+                onClick={networkInfo?.connectivity === 'portal' ? openCaptivePortal : undefined}
+              >
                 {networkInfo?.is_connected ? 
-                  <Wifi size={20} className="mx-auto mb-1 text-cyan-500" /> :
+                  <Wifi size={20} className={`mx-auto mb-1 ${networkInfo.connectivity === 'portal' || networkInfo.connectivity === 'limited' ? 'text-yellow-500' : 'text-cyan-500'}`} /> :
                   <WifiOff size={20} className="mx-auto mb-1 text-red-500" />
                 }
-                <div className="text-sm font-black text-slate-800 truncate">{networkInfo?.ssid || 'Off'}</div>
-                <div className="text-[8px] text-slate-400 uppercase font-bold">NET</div>
+                <div className="text-sm font-black text-slate-800 truncate">{networkInfo?.ssid || (networkInfo?.is_connected ? (networkInfo.primary_type === 'ethernet' ? 'Wired' : networkInfo.primary_interface) : null) || 'Off'}</div>
+                <div className="text-[8px] text-slate-400 uppercase font-bold">
+                  {networkInfo?.connectivity === 'portal' ? 'Sign in' : networkInfo?.connectivity === 'limited' ? 'No internet' : 'NET'}
+                </div>
               </div>
             </div>
           </div>
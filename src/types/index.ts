@@ -15,13 +15,110 @@ export interface BatteryInfo {
   is_charging: boolean;
   time_to_full: number | null;
   time_to_empty: number | null;
+  power: number | null;
 }
 
+export type BatteryState =
+  | 'charging'
+  | 'discharging'
+  | 'full'
+  | 'empty'
+  | 'pending-charge'
+  | 'pending-discharge'
+  | 'unknown';
+
+export interface BatteryDevice {
+  id: string;
+  kind: 'battery' | 'ups';
+  vendor: string | null;
+  model: string | null;
+  serial: string | null;
+  technology: string;
+  percentage: number;
+  state: BatteryState;
+  is_charging: boolean;
+  energy: number | null;
+  energy_full: number | null;
+  energy_full_design: number | null;
+  health: number | null;
+  power: number | null;
+  voltage: number | null;
+  cycle_count: number | null;
+  time_to_full: number | null;
+  time_to_empty: number | null;
+}
+
+export interface PowerStatus {
+  aggregate: BatteryInfo | null;
+  batteries: BatteryDevice[];
+  ac_online: boolean | null;
+  on_battery: boolean;
+}
+
+export interface BatteryAlert {
+  kind: 'plugged' | 'unplugged' | 'low' | 'critical';
+  percentage: number;
+  level: number | null;
+  action: string | null;
+}
+
+export interface ChargeThresholds {
+  battery: string;
+  start: number | null;
+  end: number | null;
+  start_supported: boolean;
+  end_supported: boolean;
+}
+
+export type PowerProfileName = 'power-saver' | 'balanced' | 'performance';
+
+export interface PowerProfile {
+  name: PowerProfileName;
+  driver: string | null;
+}
+
+export interface ProfileHold {
+  profile: PowerProfileName;
+  reason: string;
+  application_id: string;
+}
+
+export interface PowerProfiles {
+  active: PowerProfileName;
+  profiles: PowerProfile[];
+  degraded: string | null;
+  holds: ProfileHold[];
+}
+
+export interface BrightnessDevice {
+  id: string;
+  name: string;
+  kind: 'display' | 'keyboard';
+  backend: 'sysfs' | 'ddc';
+  backlight_type: 'firmware' | 'platform' | 'raw' | null;
+  current: number;
+  max: number;
+  percent: number;
+}
+
+export type ConnectivityState = 'unknown' | 'none' | 'portal' | 'limited' | 'full';
+
 export interface NetworkInfo {
   is_connected: boolean;
+  connectivity: ConnectivityState;
   ssid: string | null;
   signal_strength: number | null;
   ip_address: string | null;
+  primary_interface: string | null;
+  primary_type: string | null;
+  interfaces: NetworkDevice[];
+  vpns: VpnTunnel[];
+}
+
+export interface ConnectivityInfo {
+  state: ConnectivityState;
+  check_enabled: boolean;
+  portal_url: string | null;
 }
 
 export interface DiskInfo {
@@ -36,11 +133,70 @@ export interface DiskInfo {
 export interface AudioInfo {
   volume: number;
   is_muted: boolean;
+  mic_volume: number | null;
+  is_mic_muted: boolean;
   current_track: string | null;
   current_artist: string | null;
   is_playing: boolean;
 }
 
+export interface AudioPort {
+  name: string;
+  description: string;
+  available: boolean;
+}
+
+export interface AudioDevice {
+  index: number;
+  name: string;
+  description: string;
+  kind: 'sink' | 'source';
+  volume: number;
+  is_muted: boolean;
+  is_default: boolean;
+  ports: AudioPort[];
+  active_port: string | null;
+}
+
+export interface AudioDevices {
+  sinks: AudioDevice[];
+  sources: AudioDevice[];
+}
+
+export interface AudioStream {
+  index: number;
+  app_name: string;
+  media_name: string | null;
+  icon: string | null;
+  volume: number;
+  is_muted: boolean;
+  is_corked: boolean;
+  sink_index: number;
+  sink_name: string | null;
+}
+
+export interface MediaState {
+  player_id: string;
+  identity: string;
+  status: 'Playing' | 'Paused' | 'Stopped';
+  title: string | null;
+  artist: string | null;
+  album: string | null;
+  art_url: string | null;
+  art_path: string | null;
+  length_ms: number | null;
+  position_ms: number | null;
+  rate: number;
+  shuffle: boolean | null;
+  loop_status: 'None' | 'Track' | 'Playlist' | null;
+  can_seek: boolean;
+}
+
+export interface MediaChanged {
+  player_id: string;
+  state: MediaState | null;
+}
+
 export interface DesktopApp {
   id: string;
   name: string;
@@ -59,6 +215,60 @@ export interface FileEntry {
   icon: string;
 }
 
+export type ScheduledPowerAction = 'shutdown' | 'reboot' | 'suspend' | 'hibernate';
+
+export interface ScheduledAction {
+  id: number;
+  action: ScheduledPowerAction;
+  at: number;
+  created_at: number;
+  warned: boolean;
+}
+
+export interface ScheduleFailure {
+  scheduled: ScheduledAction;
+  reason: 'missed' | 'failed';
+  error: string | null;
+}
+
+export type InhibitWhat = 'idle' | 'sleep';
+
+export interface InhibitLock {
+  what: InhibitWhat[];
+  reason: string;
+  since: number;
+  expires_at: number | null;
+}
+
+export interface InhibitStatus {
+  lock: InhibitLock | null;
+  media_player: string | null;
+  staying_awake: boolean;
+}
+
+export interface Inhibitor {
+  what: string[];
+  who: string;
+  why: string;
+  mode: 'block' | 'delay';
+  uid: number;
+  pid: number;
+}
+
+export type NightLightSchedule = 'manual' | 'fixed' | 'sunset';
+
+export interface NightLightStatus {
+  enabled: boolean;
+  active: boolean;
+  temperature: number;
+  schedule: NightLightSchedule;
+  from: number;
+  to: number;
+  sunset: number | null;
+  sunrise: number | null;
+  backend: 'gnome' | 'gammastep' | 'redshift' | 'none';
+}
+
 export interface DesktopSettings {
   wallpaper: string;
   wallpaper_opacity: number;
@@ -76,17 +286,193 @@ export interface DesktopSettings {
   animation_speed: number;
   blur_enabled: boolean;
   transparency: number;
+  media_player: string | null;
+  charge_start_threshold: number | null;
+  charge_end_threshold: number | null;
+  battery_warning_levels: number[];
+  battery_critical_level: number;
+  battery_critical_action: 'none' | 'suspend' | 'hibernate' | 'shutdown';
+  power_notifications: boolean;
+  // Read-only: save_settings keeps the saved night light values. Use the
+  // set_night_light and set_night_light_schedule commands instead.
+  night_light_enabled: boolean;
+  night_light_temperature: number;
+  night_light_schedule: NightLightSchedule;
+  night_light_from: number;
+  night_light_to: number;
+  night_light_latitude: number | null;
+  night_light_longitude: number | null;
+}
+
+export interface MediaPlayerInfo {
+  id: string;
+  identity: string;
+  status: 'Playing' | 'Paused' | 'Stopped';
+  title: string | null;
+  artist: string | null;
+  is_selected: boolean;
 }
 
 export interface WifiNetwork {
   ssid: string;
-  signal: string;
+  bssid: string;
+  signal_strength: number;
+  frequency: number;
   security: string;
+  is_active: boolean;
+}
+
+export interface WifiRadioState {
+  enabled: boolean;
+  hardware_enabled: boolean;
+}
+
+export interface WifiScanStatus {
+  scanning: boolean;
+  elapsed_secs: number | null;
+}
+
+export interface Ipv4Settings {
+  method: 'auto' | 'manual' | 'link-local' | 'shared' | 'disabled' | string;
+  addresses: string[];
+  gateway: string | null;
+  dns: string[];
+}
+
+export interface Ipv6Settings {
+  method: 'auto' | 'dhcp' | 'manual' | 'link-local' | 'shared' | 'ignore' | 'disabled' | string;
+  addresses: string[];
+  gateway: string | null;
+  dns: string[];
+}
+
+export interface SavedConnection {
+  id: string;
+  uuid: string;
+  connection_type: string;
+  ssid: string | null;
+  autoconnect: boolean;
+  autoconnect_priority: number;
+  is_active: boolean;
+  last_used: number | null;
+  ipv4: Ipv4Settings;
+  ipv6: Ipv6Settings;
+}
+
+export interface TrafficSample {
+  timestamp: number;
+  rx_rate: number;
+  tx_rate: number;
+}
+
+export interface InterfaceTraffic {
+  interface: string;
+  samples: TrafficSample[];
+}
+
+export interface UsageTotals {
+  rx_bytes: number;
+  tx_bytes: number;
+}
+
+export interface PeriodUsage extends UsageTotals {
+  period: string;
+}
+
+export interface DataUsage {
+  today: UsageTotals;
+  this_month: UsageTotals;
+  daily: PeriodUsage[];
+  monthly: PeriodUsage[];
+  interfaces: string[];
+}
+
+export interface HotspotStatus {
+  active: boolean;
+  ssid: string | null;
+  interface: string | null;
+}
+
+export interface WifiShareCode {
+  ssid: string;
+  security: 'WPA' | 'SAE' | 'WEP' | 'nopass';
+  password: string | null;
+  payload: string;
+  svg: string;
+}
+
+export interface NetworkDevice {
+  interface: string;
+  device_type: string;
+  state: string;
+  hw_address: string | null;
+  connection: string | null;
+  ipv4_addresses: string[];
+  ipv6_addresses: string[];
+  gateway: string | null;
+  dns: string[];
+}
+
+export interface VpnTunnel {
+  name: string;
+  vpn_type: 'vpn' | 'wireguard';
+  interface: string | null;
+  state: string;
+}
+
+export interface ActiveNetworkConnection {
+  id: string;
+  uuid: string;
+  connection_type: string;
+  interfaces: string[];
+  is_default: boolean;
+  is_vpn: boolean;
+}
+
+export interface BluetoothAdapter {
+  name: string;
+  address: string;
+  alias: string;
+  powered: boolean;
+  discovering: boolean;
+  discoverable: boolean;
+  pairable: boolean;
 }
 
 export interface BluetoothDevice {
-  mac: string;
+  address: string;
   name: string;
+  icon: string | null;
+  adapter: string;
+  paired: boolean;
+  trusted: boolean;
+  connected: boolean;
+  blocked: boolean;
+  rssi: number | null;
+  battery: number | null;
+}
+
+export interface BluetoothBatteryWarning {
+  device: BluetoothDevice;
+  threshold: number;
+}
+
+export type BluetoothAgentRequestKind =
+  | 'pin-code'
+  | 'passkey'
+  | 'confirm-passkey'
+  | 'authorize'
+  | 'authorize-service'
+  | 'display-pin-code'
+  | 'display-passkey';
+
+export interface BluetoothAgentRequest {
+  id: number;
+  kind: BluetoothAgentRequestKind;
+  address: string | null;
+  name: string | null;
+  code: string | null;
+  service: string | null;
 }
 
 // Window types